@@ -1,10 +1,17 @@
+use crate::ci::CiProvider;
 use crate::date_time::DEFINE_SOURCE_DATE_EPOCH;
+use crate::git::git_rerun_paths;
 use crate::hook::HookExt;
 use crate::shadow::DEFINE_SHADOW_RS;
-use crate::{SdResult, Shadow, CARGO_METADATA};
+use crate::vcs::VcsBackend;
+use crate::{
+    DependencySource, GitScope, SdResult, Shadow, TagCheck, TagPattern, VersionScheme,
+    CARGO_METADATA, DEPENDENCY_CHECKSUMS, GIT_CHANGELOG, GIT_CHANGELOG_ENTRIES,
+};
 use is_debug::is_debug;
 use std::collections::BTreeSet;
 use std::fmt::{Display, Formatter};
+use std::path::Path;
 
 /// `shadow-rs` build constant identifiers.
 pub type ShadowConst = &'static str;
@@ -13,12 +20,23 @@ pub type ShadowConst = &'static str;
 /// membership and resolved dependencies for the current package, storing this data can result in
 /// significantly larger crate sizes. As such, the CARGO_METADATA const is disabled by default.
 ///
+/// The changelog constants GIT_CHANGELOG and GIT_CHANGELOG_ENTRIES are disabled by default as well,
+/// since they embed commit subjects into the binary.
+///
+/// The DEPENDENCY_CHECKSUMS const is disabled by default too, as the checksums are rarely needed
+/// and double the size of the dependency list.
+///
 /// Should you choose to retain this information, you have the option to customize a deny_const
 /// object and override the `new_deny` method parameters accordingly.
 ///
 #[allow(clippy::all, clippy::pedantic, clippy::restriction, clippy::nursery)]
 pub fn default_deny() -> BTreeSet<ShadowConst> {
-    BTreeSet::from([CARGO_METADATA])
+    BTreeSet::from([
+        CARGO_METADATA,
+        DEPENDENCY_CHECKSUMS,
+        GIT_CHANGELOG,
+        GIT_CHANGELOG_ENTRIES,
+    ])
 }
 
 /// Serialized values for build constants.
@@ -60,6 +78,24 @@ impl ConstVal {
         }
     }
 
+    pub fn new_tuples<S: Into<String>>(desc: S, arity: usize) -> ConstVal {
+        // Creates a new `ConstVal` with an empty slice as its value and `StrTuples` as its type.
+        ConstVal {
+            desc: desc.into(),
+            v: "[]".to_string(),
+            t: ConstType::StrTuples(arity),
+        }
+    }
+
+    pub fn new_str_slice<S: Into<String>>(desc: S) -> ConstVal {
+        // Creates a new `ConstVal` with an empty slice as its value and `StrSlice` as its type.
+        ConstVal {
+            desc: desc.into(),
+            v: "[]".to_string(),
+            t: ConstType::StrSlice,
+        }
+    }
+
     pub fn new_usize<S: Into<String>>(desc: S) -> ConstVal {
         // Creates a new `ConstVal` with an empty 0 as its value and `Usize` as its type.
         ConstVal {
@@ -83,6 +119,11 @@ pub enum ConstType {
     Usize,
     /// [`i64`].
     Int,
+    /// A slice of `&str` tuples with the given number of fields, e.g. `&[(&str, &str)]`.
+    /// The value is serialized with [`str_tuples`].
+    StrTuples(usize),
+    /// A slice of `&str`, i.e. `&[&str]`. The value is serialized with [`str_slice`].
+    StrSlice,
 }
 
 impl Display for ConstType {
@@ -93,10 +134,32 @@ impl Display for ConstType {
             ConstType::Slice => write!(f, "&[u8]"),
             ConstType::Usize => write!(f, "usize"),
             ConstType::Int => write!(f, "i64"),
+            ConstType::StrTuples(arity) => {
+                write!(f, "&[({})]", vec!["&str"; *arity].join(", "))
+            }
+            ConstType::StrSlice => write!(f, "&[&str]"),
         }
     }
 }
 
+/// Serializes `rows` as the value of a [`ConstType::StrTuples`] constant, e.g. `[("a", "b"), ("c", "d")]`.
+pub(crate) fn str_tuples<const N: usize, S: AsRef<str>>(rows: &[[S; N]]) -> String {
+    let rows: Vec<String> = rows
+        .iter()
+        .map(|row| {
+            let fields: Vec<String> = row.iter().map(|x| format!("{:?}", x.as_ref())).collect();
+            format!("({})", fields.join(", "))
+        })
+        .collect();
+    format!("[{}]", rows.join(", "))
+}
+
+/// Serializes `items` as the value of a [`ConstType::StrSlice`] constant, e.g. `["a", "b"]`.
+pub(crate) fn str_slice<S: AsRef<str>>(items: &[S]) -> String {
+    let items: Vec<String> = items.iter().map(|x| format!("{:?}", x.as_ref())).collect();
+    format!("[{}]", items.join(", "))
+}
+
 /// The BuildPattern enum defines strategies for triggering package rebuilding.
 ///
 /// Default mode is `Lazy`.
@@ -106,6 +169,8 @@ impl Display for ConstType {
 ///   If the environment is set to `release`, it behaves the same as the `RealTime` mode.
 /// * `RealTime`: The real-time mode. It will always trigger rebuilding a package upon any change,
 ///   regardless of whether the Rust environment is set to `debug` or `release`.
+///   This includes changes to the git state: `HEAD`, the ref it points to, `packed-refs`,
+///   the index and the tags, resolved through the gitdir of a linked worktree or submodule.
 /// * `Custom`: The custom build mode, an enhanced version of `RealTime` mode, allowing for user-defined conditions
 ///   to trigger rebuilding a package.
 ///
@@ -160,6 +225,27 @@ impl BuildPattern {
         println!("cargo:rerun-if-env-changed={DEFINE_SOURCE_DATE_EPOCH}");
         println!("cargo:rerun-if-changed={out_dir}/{DEFINE_SHADOW_RS}");
     }
+
+    /// Determines when Cargo should rerun the build script based on the git state of the repository
+    /// containing `src_path`. Like [`BuildPattern::rerun_if`], the lazy mode skips this in debug.
+    pub(crate) fn rerun_if_git_changed(&self, src_path: &Path) {
+        if matches!(self, BuildPattern::Lazy) && is_debug() {
+            return;
+        }
+        git_rerun_paths(src_path)
+            .iter()
+            .for_each(|p| println!("cargo:rerun-if-changed={}", p.display()));
+    }
+
+    /// Tells Cargo to rerun the build script when `Cargo.lock` changes, so that the dependency
+    /// constants follow `cargo update`. Like [`BuildPattern::rerun_if_git_changed`], this is skipped
+    /// for `Lazy` in debug mode.
+    pub(crate) fn rerun_if_lock_changed(&self, cargo_lock: &Path) {
+        if matches!(self, BuildPattern::Lazy) && is_debug() {
+            return;
+        }
+        println!("cargo:rerun-if-changed={}", cargo_lock.display());
+    }
 }
 
 /// A builder pattern structure to construct a `Shadow` instance.
@@ -174,9 +260,27 @@ impl BuildPattern {
 /// * `deny_const`: A set of build constant identifiers that should not be included in the build.
 /// * `src_path`: The source path from which files are read for building.
 /// * `out_path`: The output path where generated files will be placed.
+/// * `vcs_backend`: An optional version control backend. If not set, it is detected from the repository at `src_path`.
+/// * `ci_providers`: Additional CI providers, detected before the built-in ones.
+/// * `git_scope`: The part of the repository that the commit and working tree constants describe.
+/// * `tag_pattern`: Selects the tags used for the tag constants.
+/// * `version_scheme`: The format of the `GIT_SEMVER` constant.
+/// * `tag_check`: What to do when the tag does not match the package version.
+/// * `changelog_limit`: The maximum number of commits in the changelog constants.
+/// * `git_remote`: The name of the Git remote used for the remote constants.
+/// * `dependency_source`: Whether `cargo tree` is run, or only `Cargo.lock` is read.
 ///
 pub struct ShadowBuilder<'a> {
     hook: Option<Box<dyn HookExt + 'a>>,
+    vcs_backend: Option<Box<dyn VcsBackend + 'a>>,
+    ci_providers: Vec<Box<dyn CiProvider + 'a>>,
+    git_scope: GitScope,
+    tag_pattern: TagPattern,
+    version_scheme: VersionScheme,
+    tag_check: TagCheck,
+    changelog_limit: usize,
+    git_remote: String,
+    dependency_source: DependencySource,
     build_pattern: BuildPattern,
     deny_const: BTreeSet<ShadowConst>,
     src_path: Option<String>,
@@ -188,6 +292,15 @@ impl<'a> ShadowBuilder<'a> {
     ///
     /// Initializes the builder with the following defaults:
     /// - `hook`: None
+    /// - `vcs_backend`: None
+    /// - `ci_providers`: empty, only the built-in CI providers are detected
+    /// - `git_scope`: `GitScope::Repository`
+    /// - `tag_pattern`: all tags, without a strip-prefix
+    /// - `version_scheme`: `VersionScheme::Semver`
+    /// - `tag_check`: `TagCheck::Off`
+    /// - `changelog_limit`: 50
+    /// - `git_remote`: `origin`
+    /// - `dependency_source`: `DependencySource::CargoTree`
     /// - `build_pattern`: `BuildPattern::Lazy`
     /// - `deny_const`: Uses the result from `default_deny()`
     /// - `src_path`: Attempts to get the manifest directory using `CARGO_MANIFEST_DIR` environment variable.
@@ -201,6 +314,15 @@ impl<'a> ShadowBuilder<'a> {
         let default_out_path = std::env::var("OUT_DIR").ok();
         Self {
             hook: None,
+            vcs_backend: None,
+            ci_providers: vec![],
+            git_scope: GitScope::default(),
+            tag_pattern: TagPattern::default(),
+            version_scheme: VersionScheme::default(),
+            tag_check: TagCheck::default(),
+            changelog_limit: 50,
+            git_remote: "origin".to_string(),
+            dependency_source: DependencySource::default(),
             build_pattern: BuildPattern::default(),
             deny_const: default_deny(),
             src_path: default_src_path,
@@ -222,6 +344,139 @@ impl<'a> ShadowBuilder<'a> {
         self
     }
 
+    /// Sets the version control backend for this builder.
+    ///
+    /// # Arguments
+    ///
+    /// * `backend` - An object implementing the `VcsBackend` trait, e.g. `GitBackend`, `HgBackend` or `JjBackend`.
+    ///
+    /// # Returns
+    ///
+    /// A new `ShadowBuilder` instance with the specified version control backend.
+    pub fn vcs_backend(mut self, backend: impl VcsBackend + 'a) -> Self {
+        self.vcs_backend = Some(Box::new(backend));
+        self
+    }
+
+    /// Adds a CI provider to this builder, which maps the environment of a CI system to
+    /// [`CI_NAME`](crate::CI_NAME), [`BRANCH`](crate::BRANCH) and [`TAG`](crate::TAG).
+    ///
+    /// # Arguments
+    ///
+    /// * `provider` - An object implementing the `CiProvider` trait. Added providers are detected
+    ///   before the built-in ones, in the order they were added.
+    ///
+    /// # Returns
+    ///
+    /// A new `ShadowBuilder` instance with the specified CI provider added.
+    pub fn ci_provider(mut self, provider: impl CiProvider + 'a) -> Self {
+        self.ci_providers.push(Box::new(provider));
+        self
+    }
+
+    /// Sets the git scope for this builder.
+    ///
+    /// # Arguments
+    ///
+    /// * `scope` - A `GitScope` that limits the commit and working tree constants to a part of the repository.
+    ///
+    /// # Returns
+    ///
+    /// A new `ShadowBuilder` instance with the specified git scope.
+    pub fn git_scope(mut self, scope: GitScope) -> Self {
+        self.git_scope = scope;
+        self
+    }
+
+    /// Sets the tag pattern for this builder.
+    ///
+    /// # Arguments
+    ///
+    /// * `pattern` - A `TagPattern` that selects the tags used for `TAG`, `LAST_TAG` and `COMMITS_SINCE_TAG`,
+    ///   and optionally strips a prefix from them.
+    ///
+    /// # Returns
+    ///
+    /// A new `ShadowBuilder` instance with the specified tag pattern.
+    pub fn tag_pattern(mut self, pattern: TagPattern) -> Self {
+        self.tag_pattern = pattern;
+        self
+    }
+
+    /// Sets the version scheme for this builder.
+    ///
+    /// # Arguments
+    ///
+    /// * `scheme` - A `VersionScheme` that determines the format of the `GIT_SEMVER` constant.
+    ///
+    /// # Returns
+    ///
+    /// A new `ShadowBuilder` instance with the specified version scheme.
+    pub fn version_scheme(mut self, scheme: VersionScheme) -> Self {
+        self.version_scheme = scheme;
+        self
+    }
+
+    /// Sets the tag check policy for this builder.
+    ///
+    /// # Arguments
+    ///
+    /// * `check` - A `TagCheck` that determines whether a build at a tag that does not match `CARGO_PKG_VERSION`
+    ///   emits a warning or fails.
+    ///
+    /// # Returns
+    ///
+    /// A new `ShadowBuilder` instance with the specified tag check policy.
+    pub fn tag_check(mut self, check: TagCheck) -> Self {
+        self.tag_check = check;
+        self
+    }
+
+    /// Sets the maximum number of commits in the changelog constants for this builder.
+    ///
+    /// # Arguments
+    ///
+    /// * `limit` - The maximum number of commits since `LAST_TAG` listed in `GIT_CHANGELOG`
+    ///   and `GIT_CHANGELOG_ENTRIES`, newest first.
+    ///
+    /// # Returns
+    ///
+    /// A new `ShadowBuilder` instance with the specified changelog limit.
+    pub fn changelog_limit(mut self, limit: usize) -> Self {
+        self.changelog_limit = limit;
+        self
+    }
+
+    /// Sets the Git remote for this builder.
+    ///
+    /// # Arguments
+    ///
+    /// * `remote` - The name of the remote whose URL is used for `GIT_REMOTE_URL`, `GIT_REMOTE_HOST`,
+    ///   `GIT_REMOTE_OWNER` and `GIT_REMOTE_REPO`.
+    ///
+    /// # Returns
+    ///
+    /// A new `ShadowBuilder` instance with the specified Git remote.
+    pub fn git_remote(mut self, remote: &str) -> Self {
+        self.git_remote = remote.to_string();
+        self
+    }
+
+    /// Sets the dependency source for this builder.
+    ///
+    /// # Arguments
+    ///
+    /// * `source` - A `DependencySource` that determines whether `cargo tree` is run for `CARGO_TREE`,
+    ///   or only `Cargo.lock` is read for `DEPENDENCIES`, which is faster and never touches the network.
+    ///
+    /// # Returns
+    ///
+    /// A new `ShadowBuilder` instance with the specified dependency source.
+    pub fn dependency_source(mut self, source: DependencySource) -> Self {
+        self.dependency_source = source;
+        self
+    }
+
     /// Sets the source path for this builder.
     ///
     /// # Arguments
@@ -325,6 +580,87 @@ impl<'a> ShadowBuilder<'a> {
         &self.deny_const
     }
 
+    /// Gets the git scope.
+    ///
+    /// # Returns
+    ///
+    /// A reference to the `GitScope` currently configured for this builder.
+    pub fn get_git_scope(&self) -> &GitScope {
+        &self.git_scope
+    }
+
+    /// Gets the tag pattern.
+    ///
+    /// # Returns
+    ///
+    /// A reference to the `TagPattern` currently configured for this builder.
+    pub fn get_tag_pattern(&self) -> &TagPattern {
+        &self.tag_pattern
+    }
+
+    /// Gets the version scheme.
+    ///
+    /// # Returns
+    ///
+    /// The `VersionScheme` currently configured for this builder.
+    pub fn get_version_scheme(&self) -> VersionScheme {
+        self.version_scheme
+    }
+
+    /// Gets the tag check policy.
+    ///
+    /// # Returns
+    ///
+    /// The `TagCheck` currently configured for this builder.
+    pub fn get_tag_check(&self) -> TagCheck {
+        self.tag_check
+    }
+
+    /// Gets the maximum number of commits in the changelog constants.
+    ///
+    /// # Returns
+    ///
+    /// The changelog limit currently configured for this builder.
+    pub fn get_changelog_limit(&self) -> usize {
+        self.changelog_limit
+    }
+
+    /// Gets the name of the Git remote.
+    ///
+    /// # Returns
+    ///
+    /// The Git remote currently configured for this builder.
+    pub fn get_git_remote(&self) -> &str {
+        &self.git_remote
+    }
+
+    /// Gets the dependency source.
+    ///
+    /// # Returns
+    ///
+    /// The `DependencySource` currently configured for this builder.
+    pub fn get_dependency_source(&self) -> DependencySource {
+        self.dependency_source
+    }
+
+    /// Gets the version control backend if it has been set.
+    ///
+    /// # Returns
+    ///
+    /// An option containing a reference to the backend if one is present.
+    pub fn get_vcs_backend(&self) -> Option<&dyn VcsBackend> {
+        self.vcs_backend.as_deref()
+    }
+
+    /// Gets the CI providers added to this builder.
+    ///
+    /// # Returns
+    ///
+    /// The CI providers in the order they were added, which is the order they are detected in.
+    pub fn get_ci_providers(&self) -> &[Box<dyn CiProvider + 'a>] {
+        &self.ci_providers
+    }
+
     /// Gets the build hook if it has been set.
     ///
     /// # Returns
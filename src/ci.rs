@@ -1,21 +1,1316 @@
-use std::fmt::{Display, Formatter};
+use crate::build::{ConstVal, ShadowConst};
+use std::collections::BTreeMap;
 
-/// [`CiType`] holds the types of CI environment that `shadow-rs` can detect.
-#[derive(Debug, Default)]
-pub enum CiType {
-    Github,
-    Gitlab,
-    // TODO: Recognize other CI types, especially Travis and Jenkins
-    #[default]
-    None,
+const CI_NAME_DOC: &str = r#"
+The CI system that this project was built on, as named by its [`CiProvider`], e.g. `github`, `gitlab` or `jenkins`.
+This constant will be empty if the build did not run under a known CI system."#;
+pub const CI_NAME: ShadowConst = "CI_NAME";
+
+const CI_BUILD_NUMBER_DOC: &str = r#"
+The number of the CI build that this project was built in, as shown by the CI system, e.g. `42`.
+This constant will be empty outside CI, or if the CI system does not tell."#;
+pub const CI_BUILD_NUMBER: ShadowConst = "CI_BUILD_NUMBER";
+
+const CI_PIPELINE_ID_DOC: &str = r#"
+The unique id of the CI pipeline or workflow run that this project was built in, e.g. `1234567890`.
+This constant will be empty outside CI, or if the CI system does not tell."#;
+pub const CI_PIPELINE_ID: ShadowConst = "CI_PIPELINE_ID";
+
+const CI_JOB_URL_DOC: &str = r#"
+The web URL of the CI job that this project was built in,
+e.g. `https://github.com/baoyachi/shadow-rs/actions/runs/1234567890`.
+This constant will be empty outside CI, or if the CI system does not tell."#;
+pub const CI_JOB_URL: ShadowConst = "CI_JOB_URL";
+
+const CI_RUNNER_DOC: &str = r#"
+The name of the CI runner or agent that this project was built on, e.g. `GitHub Actions 2`.
+This constant will be empty outside CI, or if the CI system does not tell."#;
+pub const CI_RUNNER: ShadowConst = "CI_RUNNER";
+
+const CI_TRIGGER_DOC: &str = r#"
+The event that triggered the CI build that this project was built in, as named by the CI system,
+e.g. `push`, `pull_request` or `schedule`.
+This constant will be empty outside CI, or if the CI system does not tell."#;
+pub const CI_TRIGGER: ShadowConst = "CI_TRIGGER";
+
+/// The metadata of a CI run, as emitted in [`CI_BUILD_NUMBER`], [`CI_PIPELINE_ID`], [`CI_JOB_URL`],
+/// [`CI_RUNNER`] and [`CI_TRIGGER`]. A field is empty if the CI system does not tell.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct CiRun {
+    pub build_number: String,
+    pub pipeline_id: String,
+    pub job_url: String,
+    pub runner: String,
+    pub trigger: String,
+}
+
+const CI_PR_NUMBER_DOC: &str = r#"
+The number of the pull request (GitHub) or merge request (GitLab) that this project was built for, e.g. `42`.
+This constant will be empty if the build is not for a pull or merge request."#;
+pub const CI_PR_NUMBER: ShadowConst = "CI_PR_NUMBER";
+
+const CI_PR_SOURCE_BRANCH_DOC: &str = r#"
+The source (head) branch of the pull or merge request that this project was built for, e.g. `feature`.
+[`BRANCH`](crate::BRANCH) is set to this branch as well.
+This constant will be empty if the build is not for a pull or merge request."#;
+pub const CI_PR_SOURCE_BRANCH: ShadowConst = "CI_PR_SOURCE_BRANCH";
+
+const CI_PR_TARGET_BRANCH_DOC: &str = r#"
+The target (base) branch of the pull or merge request that this project was built for, e.g. `master`.
+This constant will be empty if the build is not for a pull or merge request."#;
+pub const CI_PR_TARGET_BRANCH: ShadowConst = "CI_PR_TARGET_BRANCH";
+
+/// A pull or merge request that a CI run builds, as emitted in [`CI_PR_NUMBER`],
+/// [`CI_PR_SOURCE_BRANCH`] and [`CI_PR_TARGET_BRANCH`]. A field is empty if the CI system does not tell.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct CiPullRequest {
+    pub number: String,
+    pub source_branch: String,
+    pub target_branch: String,
+}
+
+/// A CI system that `shadow-rs` can detect from the build environment.
+///
+/// A provider maps the environment variables of its CI system to [`CI_NAME`], and to the
+/// [`BRANCH`](crate::BRANCH) and [`TAG`](crate::TAG) being built, which take precedence over the
+/// values read from the repository, and to the metadata of the run in [`CiRun`].
+///
+/// Providers can be added with [`ShadowBuilder::ci_provider`](crate::ShadowBuilder::ci_provider),
+/// and are tried before the built-in ones: [`GithubCi`], [`GitlabCi`], [`JenkinsCi`], [`TravisCi`],
+/// [`CircleCi`], [`AzurePipelinesCi`], [`BitbucketCi`], [`BuildkiteCi`], [`DroneCi`], [`WoodpeckerCi`],
+/// [`TeamCityCi`], [`GiteaCi`], [`ForgejoCi`] and [`AppVeyorCi`].
+pub trait CiProvider {
+    /// The name of the CI system, written to [`CI_NAME`].
+    fn name(&self) -> &str;
+
+    /// Returns `true` if the build runs under this CI system.
+    fn detect(&self, std_env: &BTreeMap<String, String>) -> bool;
+
+    /// The branch being built, or `None` for a tag build or if the CI system does not tell.
+    fn branch(&self, _std_env: &BTreeMap<String, String>) -> Option<String> {
+        None
+    }
+
+    /// The tag being built, or `None` for a branch build.
+    fn tag(&self, _std_env: &BTreeMap<String, String>) -> Option<String> {
+        None
+    }
+
+    /// The metadata of the CI run, such as its build number and job URL.
+    fn run(&self, _std_env: &BTreeMap<String, String>) -> CiRun {
+        CiRun::default()
+    }
+
+    /// The pull or merge request being built, or `None` if the build is not for one.
+    /// Its source branch takes precedence over [`CiProvider::branch`] for [`BRANCH`](crate::BRANCH).
+    fn pull_request(&self, _std_env: &BTreeMap<String, String>) -> Option<CiPullRequest> {
+        None
+    }
+}
+
+/// The built-in providers, in the order they are tried.
+/// Gitea and Forgejo set the GitHub variables as well, so they come before [`GithubCi`].
+const BUILTIN_CI_PROVIDERS: &[&dyn CiProvider] = &[
+    &ForgejoCi,
+    &GiteaCi,
+    &GithubCi,
+    &GitlabCi,
+    &JenkinsCi,
+    &TravisCi,
+    &CircleCi,
+    &AzurePipelinesCi,
+    &BitbucketCi,
+    &BuildkiteCi,
+    &WoodpeckerCi,
+    &DroneCi,
+    &TeamCityCi,
+    &AppVeyorCi,
+];
+
+/// The first of `providers`, or else of the built-in providers, that detects the build environment.
+pub(crate) fn detect_ci<'a>(
+    providers: &'a [Box<dyn CiProvider + 'a>],
+    std_env: &BTreeMap<String, String>,
+) -> Option<&'a dyn CiProvider> {
+    providers
+        .iter()
+        .map(|x| x.as_ref())
+        .chain(BUILTIN_CI_PROVIDERS.iter().copied())
+        .find(|x| x.detect(std_env))
+}
+
+pub(crate) fn new_ci(
+    ci: Option<&dyn CiProvider>,
+    std_env: &BTreeMap<String, String>,
+) -> BTreeMap<ShadowConst, ConstVal> {
+    let (name, run, pr) = match ci {
+        Some(ci) => (
+            ci.name().to_string(),
+            ci.run(std_env),
+            ci.pull_request(std_env).unwrap_or_default(),
+        ),
+        None => Default::default(),
+    };
+    let mut map = BTreeMap::new();
+    for (key, doc, v) in [
+        (CI_NAME, CI_NAME_DOC, name),
+        (CI_BUILD_NUMBER, CI_BUILD_NUMBER_DOC, run.build_number),
+        (CI_PIPELINE_ID, CI_PIPELINE_ID_DOC, run.pipeline_id),
+        (CI_JOB_URL, CI_JOB_URL_DOC, run.job_url),
+        (CI_RUNNER, CI_RUNNER_DOC, run.runner),
+        (CI_TRIGGER, CI_TRIGGER_DOC, run.trigger),
+        (CI_PR_NUMBER, CI_PR_NUMBER_DOC, pr.number),
+        (
+            CI_PR_SOURCE_BRANCH,
+            CI_PR_SOURCE_BRANCH_DOC,
+            pr.source_branch,
+        ),
+        (
+            CI_PR_TARGET_BRANCH,
+            CI_PR_TARGET_BRANCH_DOC,
+            pr.target_branch,
+        ),
+    ] {
+        let mut val = ConstVal::new(doc);
+        val.v = v;
+        map.insert(key, val);
+    }
+    map
+}
+
+/// The non-empty value of the environment variable `key`.
+fn env<'e>(std_env: &'e BTreeMap<String, String>, key: &str) -> Option<&'e str> {
+    std_env
+        .get(key)
+        .map(|x| x.as_str())
+        .filter(|x| !x.is_empty())
+}
+
+/// The value of the environment variable `key`, or an empty string.
+fn var(std_env: &BTreeMap<String, String>, key: &str) -> String {
+    env(std_env, key).unwrap_or_default().to_string()
+}
+
+/// Returns `true` if the environment variable `key` is `true`, in any case.
+fn env_true(std_env: &BTreeMap<String, String>, key: &str) -> bool {
+    env(std_env, key).is_some_and(|x| x.eq_ignore_ascii_case("true"))
+}
+
+/// The branch of a full ref like `refs/heads/main`.
+fn ref_branch(reference: Option<&str>) -> Option<String> {
+    Some(reference?.strip_prefix("refs/heads/")?.to_string())
+}
+
+/// The tag of a full ref like `refs/tags/v1.0.0`.
+fn ref_tag(reference: Option<&str>) -> Option<String> {
+    Some(reference?.strip_prefix("refs/tags/")?.to_string())
+}
+
+/// [GitHub Actions](https://docs.github.com/en/actions/reference/variables-reference),
+/// named `github`.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct GithubCi;
+
+impl CiProvider for GithubCi {
+    fn name(&self) -> &str {
+        "github"
+    }
+
+    fn detect(&self, std_env: &BTreeMap<String, String>) -> bool {
+        env_true(std_env, "GITHUB_ACTIONS")
+    }
+
+    fn branch(&self, std_env: &BTreeMap<String, String>) -> Option<String> {
+        ref_branch(env(std_env, "GITHUB_REF"))
+    }
+
+    fn tag(&self, std_env: &BTreeMap<String, String>) -> Option<String> {
+        ref_tag(env(std_env, "GITHUB_REF"))
+    }
+
+    fn run(&self, std_env: &BTreeMap<String, String>) -> CiRun {
+        let run_id = var(std_env, "GITHUB_RUN_ID");
+        let job_url = match (
+            env(std_env, "GITHUB_SERVER_URL"),
+            env(std_env, "GITHUB_REPOSITORY"),
+        ) {
+            (Some(server), Some(repository)) if !run_id.is_empty() => {
+                format!("{server}/{repository}/actions/runs/{run_id}")
+            }
+            _ => String::new(),
+        };
+        CiRun {
+            build_number: var(std_env, "GITHUB_RUN_NUMBER"),
+            pipeline_id: run_id,
+            job_url,
+            runner: var(std_env, "RUNNER_NAME"),
+            trigger: var(std_env, "GITHUB_EVENT_NAME"),
+        }
+    }
+
+    fn pull_request(&self, std_env: &BTreeMap<String, String>) -> Option<CiPullRequest> {
+        // `refs/pull/<number>/merge` for `pull_request` events; `pull_request_target` events
+        // also set `GITHUB_HEAD_REF` but build the target branch, so they are not a pull request build
+        let (number, _) = env(std_env, "GITHUB_REF")?
+            .strip_prefix("refs/pull/")?
+            .split_once('/')?;
+        Some(CiPullRequest {
+            number: number.to_string(),
+            source_branch: var(std_env, "GITHUB_HEAD_REF"),
+            target_branch: var(std_env, "GITHUB_BASE_REF"),
+        })
+    }
+}
+
+/// [Gitea Actions](https://docs.gitea.com/usage/actions/overview), named `gitea`.
+/// It sets the GitHub Actions variables, see [`GithubCi`].
+#[derive(Debug, Default, Clone, Copy)]
+pub struct GiteaCi;
+
+impl CiProvider for GiteaCi {
+    fn name(&self) -> &str {
+        "gitea"
+    }
+
+    fn detect(&self, std_env: &BTreeMap<String, String>) -> bool {
+        env_true(std_env, "GITEA_ACTIONS")
+    }
+
+    fn branch(&self, std_env: &BTreeMap<String, String>) -> Option<String> {
+        GithubCi.branch(std_env)
+    }
+
+    fn tag(&self, std_env: &BTreeMap<String, String>) -> Option<String> {
+        GithubCi.tag(std_env)
+    }
+
+    fn run(&self, std_env: &BTreeMap<String, String>) -> CiRun {
+        GithubCi.run(std_env)
+    }
+
+    fn pull_request(&self, std_env: &BTreeMap<String, String>) -> Option<CiPullRequest> {
+        GithubCi.pull_request(std_env)
+    }
+}
+
+/// [Forgejo Actions](https://forgejo.org/docs/latest/user/actions/), named `forgejo`.
+/// It sets the GitHub Actions variables, see [`GithubCi`].
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ForgejoCi;
+
+impl CiProvider for ForgejoCi {
+    fn name(&self) -> &str {
+        "forgejo"
+    }
+
+    fn detect(&self, std_env: &BTreeMap<String, String>) -> bool {
+        env_true(std_env, "FORGEJO_ACTIONS")
+    }
+
+    fn branch(&self, std_env: &BTreeMap<String, String>) -> Option<String> {
+        GithubCi.branch(std_env)
+    }
+
+    fn tag(&self, std_env: &BTreeMap<String, String>) -> Option<String> {
+        GithubCi.tag(std_env)
+    }
+
+    fn run(&self, std_env: &BTreeMap<String, String>) -> CiRun {
+        GithubCi.run(std_env)
+    }
+
+    fn pull_request(&self, std_env: &BTreeMap<String, String>) -> Option<CiPullRequest> {
+        GithubCi.pull_request(std_env)
+    }
+}
+
+/// [GitLab CI/CD](https://docs.gitlab.com/ci/variables/predefined_variables/), named `gitlab`.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct GitlabCi;
+
+impl CiProvider for GitlabCi {
+    fn name(&self) -> &str {
+        "gitlab"
+    }
+
+    fn detect(&self, std_env: &BTreeMap<String, String>) -> bool {
+        env_true(std_env, "GITLAB_CI")
+    }
+
+    fn branch(&self, std_env: &BTreeMap<String, String>) -> Option<String> {
+        if self.tag(std_env).is_some() {
+            return None;
+        }
+        env(std_env, "CI_COMMIT_REF_NAME").map(|x| x.to_string())
+    }
+
+    fn tag(&self, std_env: &BTreeMap<String, String>) -> Option<String> {
+        env(std_env, "CI_COMMIT_TAG").map(|x| x.to_string())
+    }
+
+    fn run(&self, std_env: &BTreeMap<String, String>) -> CiRun {
+        CiRun {
+            build_number: var(std_env, "CI_PIPELINE_IID"),
+            pipeline_id: var(std_env, "CI_PIPELINE_ID"),
+            job_url: var(std_env, "CI_JOB_URL"),
+            runner: var(std_env, "CI_RUNNER_DESCRIPTION"),
+            trigger: var(std_env, "CI_PIPELINE_SOURCE"),
+        }
+    }
+
+    fn pull_request(&self, std_env: &BTreeMap<String, String>) -> Option<CiPullRequest> {
+        Some(CiPullRequest {
+            number: env(std_env, "CI_MERGE_REQUEST_IID")?.to_string(),
+            source_branch: var(std_env, "CI_MERGE_REQUEST_SOURCE_BRANCH_NAME"),
+            target_branch: var(std_env, "CI_MERGE_REQUEST_TARGET_BRANCH_NAME"),
+        })
+    }
+}
+
+/// [Jenkins](https://www.jenkins.io/doc/book/pipeline/jenkinsfile/#using-environment-variables),
+/// named `jenkins`.
+///
+/// A multibranch pipeline sets `BRANCH_NAME` and `TAG_NAME`, otherwise the Git plugin sets
+/// `GIT_LOCAL_BRANCH` when checking out to a local branch, and `GIT_BRANCH` to the remote
+/// branch, e.g. `origin/main`, whose `origin/` prefix is stripped.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct JenkinsCi;
+
+impl CiProvider for JenkinsCi {
+    fn name(&self) -> &str {
+        "jenkins"
+    }
+
+    fn detect(&self, std_env: &BTreeMap<String, String>) -> bool {
+        env(std_env, "JENKINS_URL").is_some()
+    }
+
+    fn branch(&self, std_env: &BTreeMap<String, String>) -> Option<String> {
+        if self.tag(std_env).is_some() {
+            return None;
+        }
+        env(std_env, "BRANCH_NAME")
+            .or_else(|| env(std_env, "GIT_LOCAL_BRANCH"))
+            .or_else(|| env(std_env, "GIT_BRANCH").map(|x| x.strip_prefix("origin/").unwrap_or(x)))
+            .map(|x| x.to_string())
+    }
+
+    fn tag(&self, std_env: &BTreeMap<String, String>) -> Option<String> {
+        env(std_env, "TAG_NAME").map(|x| x.to_string())
+    }
+
+    fn run(&self, std_env: &BTreeMap<String, String>) -> CiRun {
+        CiRun {
+            build_number: var(std_env, "BUILD_NUMBER"),
+            pipeline_id: var(std_env, "BUILD_TAG"),
+            job_url: var(std_env, "BUILD_URL"),
+            runner: var(std_env, "NODE_NAME"),
+            trigger: String::new(),
+        }
+    }
+}
+
+/// [Travis CI](https://docs.travis-ci.com/user/environment-variables/), named `travis`.
+///
+/// For a pull request, the branch is its head branch rather than the target in `TRAVIS_BRANCH`.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct TravisCi;
+
+impl CiProvider for TravisCi {
+    fn name(&self) -> &str {
+        "travis"
+    }
+
+    fn detect(&self, std_env: &BTreeMap<String, String>) -> bool {
+        env_true(std_env, "TRAVIS")
+    }
+
+    fn branch(&self, std_env: &BTreeMap<String, String>) -> Option<String> {
+        if self.tag(std_env).is_some() {
+            return None;
+        }
+        env(std_env, "TRAVIS_PULL_REQUEST_BRANCH")
+            .or_else(|| env(std_env, "TRAVIS_BRANCH"))
+            .map(|x| x.to_string())
+    }
+
+    fn tag(&self, std_env: &BTreeMap<String, String>) -> Option<String> {
+        env(std_env, "TRAVIS_TAG").map(|x| x.to_string())
+    }
+
+    fn run(&self, std_env: &BTreeMap<String, String>) -> CiRun {
+        CiRun {
+            build_number: var(std_env, "TRAVIS_BUILD_NUMBER"),
+            pipeline_id: var(std_env, "TRAVIS_BUILD_ID"),
+            job_url: var(std_env, "TRAVIS_JOB_WEB_URL"),
+            runner: String::new(),
+            trigger: var(std_env, "TRAVIS_EVENT_TYPE"),
+        }
+    }
+}
+
+/// [CircleCI](https://circleci.com/docs/variables/), named `circleci`.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct CircleCi;
+
+impl CiProvider for CircleCi {
+    fn name(&self) -> &str {
+        "circleci"
+    }
+
+    fn detect(&self, std_env: &BTreeMap<String, String>) -> bool {
+        env_true(std_env, "CIRCLECI")
+    }
+
+    fn branch(&self, std_env: &BTreeMap<String, String>) -> Option<String> {
+        env(std_env, "CIRCLE_BRANCH").map(|x| x.to_string())
+    }
+
+    fn tag(&self, std_env: &BTreeMap<String, String>) -> Option<String> {
+        env(std_env, "CIRCLE_TAG").map(|x| x.to_string())
+    }
+
+    fn run(&self, std_env: &BTreeMap<String, String>) -> CiRun {
+        CiRun {
+            build_number: var(std_env, "CIRCLE_BUILD_NUM"),
+            pipeline_id: var(std_env, "CIRCLE_WORKFLOW_ID"),
+            job_url: var(std_env, "CIRCLE_BUILD_URL"),
+            runner: String::new(),
+            trigger: String::new(),
+        }
+    }
+}
+
+/// [Azure Pipelines](https://learn.microsoft.com/en-us/azure/devops/pipelines/build/variables),
+/// named `azure`.
+///
+/// For a pull request, the branch is its head branch in `SYSTEM_PULLREQUEST_SOURCEBRANCH`.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct AzurePipelinesCi;
+
+impl CiProvider for AzurePipelinesCi {
+    fn name(&self) -> &str {
+        "azure"
+    }
+
+    fn detect(&self, std_env: &BTreeMap<String, String>) -> bool {
+        env_true(std_env, "TF_BUILD")
+    }
+
+    fn branch(&self, std_env: &BTreeMap<String, String>) -> Option<String> {
+        let source = env(std_env, "SYSTEM_PULLREQUEST_SOURCEBRANCH");
+        ref_branch(source)
+            .or_else(|| source.map(|x| x.to_string()))
+            .or_else(|| ref_branch(env(std_env, "BUILD_SOURCEBRANCH")))
+    }
+
+    fn tag(&self, std_env: &BTreeMap<String, String>) -> Option<String> {
+        ref_tag(env(std_env, "BUILD_SOURCEBRANCH"))
+    }
+
+    fn run(&self, std_env: &BTreeMap<String, String>) -> CiRun {
+        let build_id = var(std_env, "BUILD_BUILDID");
+        let job_url = match (
+            env(std_env, "SYSTEM_COLLECTIONURI"),
+            env(std_env, "SYSTEM_TEAMPROJECT"),
+        ) {
+            (Some(collection), Some(project)) if !build_id.is_empty() => format!(
+                "{}/{project}/_build/results?buildId={build_id}",
+                collection.trim_end_matches('/')
+            ),
+            _ => String::new(),
+        };
+        CiRun {
+            build_number: var(std_env, "BUILD_BUILDNUMBER"),
+            pipeline_id: build_id,
+            job_url,
+            runner: var(std_env, "AGENT_NAME"),
+            trigger: var(std_env, "BUILD_REASON"),
+        }
+    }
+}
+
+/// [Bitbucket Pipelines](https://support.atlassian.com/bitbucket-cloud/docs/variables-and-secrets/),
+/// named `bitbucket`.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct BitbucketCi;
+
+impl CiProvider for BitbucketCi {
+    fn name(&self) -> &str {
+        "bitbucket"
+    }
+
+    fn detect(&self, std_env: &BTreeMap<String, String>) -> bool {
+        env(std_env, "BITBUCKET_BUILD_NUMBER").is_some()
+    }
+
+    fn branch(&self, std_env: &BTreeMap<String, String>) -> Option<String> {
+        env(std_env, "BITBUCKET_BRANCH").map(|x| x.to_string())
+    }
+
+    fn tag(&self, std_env: &BTreeMap<String, String>) -> Option<String> {
+        env(std_env, "BITBUCKET_TAG").map(|x| x.to_string())
+    }
+
+    fn run(&self, std_env: &BTreeMap<String, String>) -> CiRun {
+        let build_number = var(std_env, "BITBUCKET_BUILD_NUMBER");
+        let job_url = env(std_env, "BITBUCKET_GIT_HTTP_ORIGIN")
+            .map(|origin| format!("{origin}/pipelines/results/{build_number}"))
+            .unwrap_or_default();
+        CiRun {
+            build_number,
+            pipeline_id: var(std_env, "BITBUCKET_PIPELINE_UUID"),
+            job_url,
+            runner: String::new(),
+            trigger: String::new(),
+        }
+    }
+}
+
+/// [Buildkite](https://buildkite.com/docs/pipelines/configure/environment-variables),
+/// named `buildkite`.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct BuildkiteCi;
+
+impl CiProvider for BuildkiteCi {
+    fn name(&self) -> &str {
+        "buildkite"
+    }
+
+    fn detect(&self, std_env: &BTreeMap<String, String>) -> bool {
+        env_true(std_env, "BUILDKITE")
+    }
+
+    fn branch(&self, std_env: &BTreeMap<String, String>) -> Option<String> {
+        if self.tag(std_env).is_some() {
+            return None;
+        }
+        env(std_env, "BUILDKITE_BRANCH").map(|x| x.to_string())
+    }
+
+    fn tag(&self, std_env: &BTreeMap<String, String>) -> Option<String> {
+        env(std_env, "BUILDKITE_TAG").map(|x| x.to_string())
+    }
+
+    fn run(&self, std_env: &BTreeMap<String, String>) -> CiRun {
+        let job_url = match (
+            env(std_env, "BUILDKITE_BUILD_URL"),
+            env(std_env, "BUILDKITE_JOB_ID"),
+        ) {
+            (Some(url), Some(job)) => format!("{url}#{job}"),
+            (Some(url), None) => url.to_string(),
+            _ => String::new(),
+        };
+        CiRun {
+            build_number: var(std_env, "BUILDKITE_BUILD_NUMBER"),
+            pipeline_id: var(std_env, "BUILDKITE_BUILD_ID"),
+            job_url,
+            runner: var(std_env, "BUILDKITE_AGENT_NAME"),
+            trigger: var(std_env, "BUILDKITE_SOURCE"),
+        }
+    }
+}
+
+/// [Drone](https://docs.drone.io/pipeline/environment/reference/), named `drone`.
+///
+/// For a pull request, the branch is its head branch rather than the target in `DRONE_BRANCH`.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct DroneCi;
+
+impl CiProvider for DroneCi {
+    fn name(&self) -> &str {
+        "drone"
+    }
+
+    fn detect(&self, std_env: &BTreeMap<String, String>) -> bool {
+        env_true(std_env, "DRONE")
+    }
+
+    fn branch(&self, std_env: &BTreeMap<String, String>) -> Option<String> {
+        if self.tag(std_env).is_some() {
+            return None;
+        }
+        env(std_env, "DRONE_SOURCE_BRANCH")
+            .or_else(|| env(std_env, "DRONE_BRANCH"))
+            .map(|x| x.to_string())
+    }
+
+    fn tag(&self, std_env: &BTreeMap<String, String>) -> Option<String> {
+        env(std_env, "DRONE_TAG").map(|x| x.to_string())
+    }
+
+    /// Drone numbers its builds per repository and has no other id for them, so `pipeline_id` is empty.
+    fn run(&self, std_env: &BTreeMap<String, String>) -> CiRun {
+        CiRun {
+            build_number: var(std_env, "DRONE_BUILD_NUMBER"),
+            pipeline_id: String::new(),
+            job_url: var(std_env, "DRONE_BUILD_LINK"),
+            runner: var(std_env, "DRONE_RUNNER_HOSTNAME"),
+            trigger: var(std_env, "DRONE_BUILD_EVENT"),
+        }
+    }
+}
+
+/// [Woodpecker CI](https://woodpecker-ci.org/docs/usage/environment), named `woodpecker`.
+///
+/// For a pull request, the branch is its head branch rather than the target in `CI_COMMIT_BRANCH`.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct WoodpeckerCi;
+
+impl CiProvider for WoodpeckerCi {
+    fn name(&self) -> &str {
+        "woodpecker"
+    }
+
+    fn detect(&self, std_env: &BTreeMap<String, String>) -> bool {
+        env(std_env, "CI") == Some("woodpecker")
+    }
+
+    fn branch(&self, std_env: &BTreeMap<String, String>) -> Option<String> {
+        if self.tag(std_env).is_some() {
+            return None;
+        }
+        env(std_env, "CI_COMMIT_SOURCE_BRANCH")
+            .or_else(|| env(std_env, "CI_COMMIT_BRANCH"))
+            .map(|x| x.to_string())
+    }
+
+    fn tag(&self, std_env: &BTreeMap<String, String>) -> Option<String> {
+        env(std_env, "CI_COMMIT_TAG").map(|x| x.to_string())
+    }
+
+    /// Woodpecker numbers its pipelines per repository and has no other id for them, so `pipeline_id` is empty.
+    fn run(&self, std_env: &BTreeMap<String, String>) -> CiRun {
+        CiRun {
+            build_number: var(std_env, "CI_PIPELINE_NUMBER"),
+            pipeline_id: String::new(),
+            job_url: var(std_env, "CI_PIPELINE_URL"),
+            runner: String::new(),
+            trigger: var(std_env, "CI_PIPELINE_EVENT"),
+        }
+    }
+}
+
+/// [TeamCity](https://www.jetbrains.com/help/teamcity/predefined-build-parameters.html),
+/// named `teamcity`.
+///
+/// TeamCity does not pass the branch to the build environment by default, it is read from a
+/// `TEAMCITY_BUILD_BRANCH` variable, e.g. defined as the parameter
+/// `env.TEAMCITY_BUILD_BRANCH = %teamcity.build.branch%`.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct TeamCityCi;
+
+impl CiProvider for TeamCityCi {
+    fn name(&self) -> &str {
+        "teamcity"
+    }
+
+    fn detect(&self, std_env: &BTreeMap<String, String>) -> bool {
+        env(std_env, "TEAMCITY_VERSION").is_some()
+    }
+
+    fn branch(&self, std_env: &BTreeMap<String, String>) -> Option<String> {
+        let branch = env(std_env, "TEAMCITY_BUILD_BRANCH")?;
+        ref_branch(Some(branch)).or_else(|| {
+            (ref_tag(Some(branch)).is_none() && branch != "<default>").then(|| branch.to_string())
+        })
+    }
+
+    fn tag(&self, std_env: &BTreeMap<String, String>) -> Option<String> {
+        ref_tag(env(std_env, "TEAMCITY_BUILD_BRANCH"))
+    }
+
+    fn run(&self, std_env: &BTreeMap<String, String>) -> CiRun {
+        CiRun {
+            build_number: var(std_env, "BUILD_NUMBER"),
+            ..Default::default()
+        }
+    }
 }
 
-impl Display for CiType {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        match self {
-            CiType::Github => write!(f, "github"),
-            CiType::Gitlab => write!(f, "gitlab"),
-            _ => write!(f, "none"),
+/// [AppVeyor](https://www.appveyor.com/docs/environment-variables/), named `appveyor`.
+///
+/// For a pull request, the branch is its head branch rather than the target in `APPVEYOR_REPO_BRANCH`.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct AppVeyorCi;
+
+impl CiProvider for AppVeyorCi {
+    fn name(&self) -> &str {
+        "appveyor"
+    }
+
+    fn detect(&self, std_env: &BTreeMap<String, String>) -> bool {
+        env_true(std_env, "APPVEYOR")
+    }
+
+    fn branch(&self, std_env: &BTreeMap<String, String>) -> Option<String> {
+        if self.tag(std_env).is_some() {
+            return None;
+        }
+        env(std_env, "APPVEYOR_PULL_REQUEST_HEAD_REPO_BRANCH")
+            .or_else(|| env(std_env, "APPVEYOR_REPO_BRANCH"))
+            .map(|x| x.to_string())
+    }
+
+    fn tag(&self, std_env: &BTreeMap<String, String>) -> Option<String> {
+        if !env_true(std_env, "APPVEYOR_REPO_TAG") {
+            return None;
         }
+        env(std_env, "APPVEYOR_REPO_TAG_NAME").map(|x| x.to_string())
+    }
+
+    fn run(&self, std_env: &BTreeMap<String, String>) -> CiRun {
+        let build_id = var(std_env, "APPVEYOR_BUILD_ID");
+        let job_url = match (
+            env(std_env, "APPVEYOR_URL"),
+            env(std_env, "APPVEYOR_ACCOUNT_NAME"),
+            env(std_env, "APPVEYOR_PROJECT_SLUG"),
+            env(std_env, "APPVEYOR_JOB_ID"),
+        ) {
+            (Some(url), Some(account), Some(project), Some(job)) => {
+                format!("{url}/project/{account}/{project}/build/job/{job}")
+            }
+            _ => String::new(),
+        };
+        CiRun {
+            build_number: var(std_env, "APPVEYOR_BUILD_NUMBER"),
+            pipeline_id: build_id,
+            job_url,
+            runner: String::new(),
+            trigger: String::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn env_map(vars: &[(&str, &str)]) -> BTreeMap<String, String> {
+        vars.iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    /// The name, branch and tag detected from `vars`.
+    fn detect(vars: &[(&str, &str)]) -> Option<(String, Option<String>, Option<String>)> {
+        let std_env = env_map(vars);
+        let ci = detect_ci(&[], &std_env)?;
+        Some((ci.name().to_string(), ci.branch(&std_env), ci.tag(&std_env)))
+    }
+
+    fn branch(name: &str, branch: &str) -> Option<(String, Option<String>, Option<String>)> {
+        Some((name.to_string(), Some(branch.to_string()), None))
+    }
+
+    fn tag(name: &str, tag: &str) -> Option<(String, Option<String>, Option<String>)> {
+        Some((name.to_string(), None, Some(tag.to_string())))
+    }
+
+    #[test]
+    fn test_detect_builtin_ci() {
+        assert_eq!(detect(&[]), None);
+        assert_eq!(
+            detect(&[
+                ("GITHUB_ACTIONS", "true"),
+                ("GITHUB_REF", "refs/heads/master")
+            ]),
+            branch("github", "master")
+        );
+        assert_eq!(
+            detect(&[
+                ("GITHUB_ACTIONS", "true"),
+                ("GITHUB_REF", "refs/tags/v1.0.0")
+            ]),
+            tag("github", "v1.0.0")
+        );
+        assert_eq!(
+            detect(&[
+                ("GITHUB_ACTIONS", "true"),
+                ("GITEA_ACTIONS", "true"),
+                ("GITHUB_REF", "refs/heads/main")
+            ]),
+            branch("gitea", "main")
+        );
+        assert_eq!(
+            detect(&[
+                ("GITHUB_ACTIONS", "true"),
+                ("GITEA_ACTIONS", "true"),
+                ("FORGEJO_ACTIONS", "true"),
+                ("GITHUB_REF", "refs/tags/v1.0.0")
+            ]),
+            tag("forgejo", "v1.0.0")
+        );
+        assert_eq!(
+            detect(&[
+                ("GITLAB_CI", "true"),
+                ("CI_COMMIT_REF_NAME", "v1.0.0"),
+                ("CI_COMMIT_TAG", "v1.0.0")
+            ]),
+            tag("gitlab", "v1.0.0")
+        );
+        assert_eq!(
+            detect(&[("GITLAB_CI", "true"), ("CI_COMMIT_REF_NAME", "develop")]),
+            branch("gitlab", "develop")
+        );
+        assert_eq!(
+            detect(&[
+                ("JENKINS_URL", "https://ci.example.com/"),
+                ("GIT_BRANCH", "origin/feature/a")
+            ]),
+            branch("jenkins", "feature/a")
+        );
+        assert_eq!(
+            detect(&[
+                ("JENKINS_URL", "https://ci.example.com/"),
+                ("GIT_BRANCH", "feature/a")
+            ]),
+            branch("jenkins", "feature/a")
+        );
+        assert_eq!(
+            detect(&[
+                ("JENKINS_URL", "https://ci.example.com/"),
+                ("GIT_LOCAL_BRANCH", "develop"),
+                ("GIT_BRANCH", "origin/develop")
+            ]),
+            branch("jenkins", "develop")
+        );
+        assert_eq!(
+            detect(&[
+                ("JENKINS_URL", "https://ci.example.com/"),
+                ("BRANCH_NAME", "v1.0.0"),
+                ("TAG_NAME", "v1.0.0")
+            ]),
+            tag("jenkins", "v1.0.0")
+        );
+        assert_eq!(
+            detect(&[
+                ("TRAVIS", "true"),
+                ("TRAVIS_BRANCH", "master"),
+                ("TRAVIS_PULL_REQUEST_BRANCH", "feature")
+            ]),
+            branch("travis", "feature")
+        );
+        assert_eq!(
+            detect(&[
+                ("TRAVIS", "true"),
+                ("TRAVIS_BRANCH", "v1.0.0"),
+                ("TRAVIS_TAG", "v1.0.0")
+            ]),
+            tag("travis", "v1.0.0")
+        );
+        assert_eq!(
+            detect(&[("CIRCLECI", "true"), ("CIRCLE_BRANCH", "main")]),
+            branch("circleci", "main")
+        );
+        assert_eq!(
+            detect(&[
+                ("TF_BUILD", "True"),
+                ("BUILD_SOURCEBRANCH", "refs/heads/main")
+            ]),
+            branch("azure", "main")
+        );
+        assert_eq!(
+            detect(&[
+                ("TF_BUILD", "True"),
+                ("BUILD_SOURCEBRANCH", "refs/pull/7/merge"),
+                ("SYSTEM_PULLREQUEST_SOURCEBRANCH", "refs/heads/feature")
+            ]),
+            branch("azure", "feature")
+        );
+        assert_eq!(
+            detect(&[
+                ("TF_BUILD", "True"),
+                ("BUILD_SOURCEBRANCH", "refs/tags/v1.0.0")
+            ]),
+            tag("azure", "v1.0.0")
+        );
+        assert_eq!(
+            detect(&[
+                ("BITBUCKET_BUILD_NUMBER", "42"),
+                ("BITBUCKET_TAG", "v1.0.0")
+            ]),
+            tag("bitbucket", "v1.0.0")
+        );
+        assert_eq!(
+            detect(&[("BUILDKITE", "true"), ("BUILDKITE_BRANCH", "main")]),
+            branch("buildkite", "main")
+        );
+        assert_eq!(
+            detect(&[
+                ("CI", "woodpecker"),
+                ("CI_COMMIT_BRANCH", "main"),
+                ("CI_COMMIT_SOURCE_BRANCH", "feature")
+            ]),
+            branch("woodpecker", "feature")
+        );
+        assert_eq!(
+            detect(&[
+                ("DRONE", "true"),
+                ("DRONE_BRANCH", "main"),
+                ("DRONE_TAG", "v1.0.0")
+            ]),
+            tag("drone", "v1.0.0")
+        );
+        assert_eq!(
+            detect(&[
+                ("TEAMCITY_VERSION", "2025.07"),
+                ("TEAMCITY_BUILD_BRANCH", "refs/heads/main")
+            ]),
+            branch("teamcity", "main")
+        );
+        assert_eq!(
+            detect(&[("TEAMCITY_VERSION", "2025.07")]),
+            Some(("teamcity".to_string(), None, None))
+        );
+        assert_eq!(
+            detect(&[
+                ("APPVEYOR", "True"),
+                ("APPVEYOR_REPO_BRANCH", "master"),
+                ("APPVEYOR_REPO_TAG", "true"),
+                ("APPVEYOR_REPO_TAG_NAME", "v1.0.0")
+            ]),
+            tag("appveyor", "v1.0.0")
+        );
+    }
+
+    #[test]
+    fn test_custom_ci_provider() {
+        struct Concourse;
+
+        impl CiProvider for Concourse {
+            fn name(&self) -> &str {
+                "concourse"
+            }
+
+            fn detect(&self, std_env: &BTreeMap<String, String>) -> bool {
+                std_env.contains_key("BUILD_PIPELINE_NAME")
+            }
+
+            fn branch(&self, std_env: &BTreeMap<String, String>) -> Option<String> {
+                std_env.get("BUILD_PIPELINE_INSTANCE_BRANCH").cloned()
+            }
+        }
+
+        let providers: Vec<Box<dyn CiProvider>> = vec![Box::new(Concourse)];
+        let std_env = env_map(&[
+            ("GITHUB_ACTIONS", "true"),
+            ("BUILD_PIPELINE_NAME", "release"),
+            ("BUILD_PIPELINE_INSTANCE_BRANCH", "main"),
+        ]);
+        // a custom provider is tried before the built-in ones
+        let ci = detect_ci(&providers, &std_env).unwrap();
+        assert_eq!(ci.name(), "concourse");
+        assert_eq!(ci.branch(&std_env), Some("main".to_string()));
+        assert_eq!(ci.tag(&std_env), None);
+        let map = new_ci(Some(ci), &std_env);
+        assert_eq!(map.get(CI_NAME).unwrap().v, "concourse");
+        assert_eq!(map.get(CI_BUILD_NUMBER).unwrap().v, "");
+
+        let std_env = env_map(&[("GITHUB_ACTIONS", "true")]);
+        let ci = detect_ci(&providers, &std_env);
+        assert_eq!(ci.map(|x| x.name()), Some("github"));
+        let map = new_ci(None, &std_env);
+        assert_eq!(map.len(), 9);
+        assert!(map.values().all(|x| x.v.is_empty()));
+    }
+
+    #[test]
+    fn test_ci_pull_request() {
+        let std_env = env_map(&[
+            ("GITHUB_ACTIONS", "true"),
+            ("GITHUB_REF", "refs/pull/42/merge"),
+            ("GITHUB_HEAD_REF", "feature"),
+            ("GITHUB_BASE_REF", "master"),
+        ]);
+        let pr = CiPullRequest {
+            number: "42".to_string(),
+            source_branch: "feature".to_string(),
+            target_branch: "master".to_string(),
+        };
+        assert_eq!(GithubCi.pull_request(&std_env), Some(pr.clone()));
+        assert_eq!(GithubCi.branch(&std_env), None);
+        let map = new_ci(Some(&GithubCi), &std_env);
+        assert_eq!(map.get(CI_PR_NUMBER).unwrap().v, "42");
+        assert_eq!(map.get(CI_PR_SOURCE_BRANCH).unwrap().v, "feature");
+        assert_eq!(map.get(CI_PR_TARGET_BRANCH).unwrap().v, "master");
+
+        // `pull_request_target` builds the target branch
+        let std_env = env_map(&[
+            ("GITHUB_ACTIONS", "true"),
+            ("GITHUB_EVENT_NAME", "pull_request_target"),
+            ("GITHUB_REF", "refs/heads/master"),
+            ("GITHUB_HEAD_REF", "feature"),
+            ("GITHUB_BASE_REF", "master"),
+        ]);
+        assert_eq!(GithubCi.pull_request(&std_env), None);
+        assert_eq!(GithubCi.branch(&std_env).as_deref(), Some("master"));
+        let map = new_ci(Some(&GithubCi), &std_env);
+        assert_eq!(map.get(CI_PR_SOURCE_BRANCH).unwrap().v, "");
+        let std_env = env_map(&[
+            ("GITHUB_ACTIONS", "true"),
+            ("GITHUB_REF", "refs/heads/master"),
+        ]);
+        assert_eq!(GithubCi.pull_request(&std_env), None);
+
+        let std_env = env_map(&[
+            ("GITLAB_CI", "true"),
+            ("CI_COMMIT_REF_NAME", "feature"),
+            ("CI_MERGE_REQUEST_IID", "42"),
+            ("CI_MERGE_REQUEST_SOURCE_BRANCH_NAME", "feature"),
+            ("CI_MERGE_REQUEST_TARGET_BRANCH_NAME", "master"),
+        ]);
+        assert_eq!(GitlabCi.pull_request(&std_env), Some(pr));
+        let std_env = env_map(&[("GITLAB_CI", "true"), ("CI_COMMIT_REF_NAME", "master")]);
+        assert_eq!(GitlabCi.pull_request(&std_env), None);
+    }
+
+    #[test]
+    fn test_ci_run() {
+        let std_env = env_map(&[
+            ("GITHUB_ACTIONS", "true"),
+            ("GITHUB_RUN_ID", "1234567890"),
+            ("GITHUB_RUN_NUMBER", "42"),
+            ("GITHUB_SERVER_URL", "https://github.com"),
+            ("GITHUB_REPOSITORY", "baoyachi/shadow-rs"),
+            ("GITHUB_EVENT_NAME", "push"),
+            ("RUNNER_NAME", "GitHub Actions 2"),
+        ]);
+        let map = new_ci(detect_ci(&[], &std_env), &std_env);
+        assert_eq!(map.get(CI_NAME).unwrap().v, "github");
+        assert_eq!(map.get(CI_BUILD_NUMBER).unwrap().v, "42");
+        assert_eq!(map.get(CI_PIPELINE_ID).unwrap().v, "1234567890");
+        assert_eq!(
+            map.get(CI_JOB_URL).unwrap().v,
+            "https://github.com/baoyachi/shadow-rs/actions/runs/1234567890"
+        );
+        assert_eq!(map.get(CI_RUNNER).unwrap().v, "GitHub Actions 2");
+        assert_eq!(map.get(CI_TRIGGER).unwrap().v, "push");
+
+        let std_env = env_map(&[
+            ("GITLAB_CI", "true"),
+            ("CI_PIPELINE_IID", "7"),
+            ("CI_PIPELINE_ID", "987654"),
+            ("CI_JOB_URL", "https://gitlab.com/group/project/-/jobs/123"),
+            ("CI_RUNNER_DESCRIPTION", "shared-runner"),
+            ("CI_PIPELINE_SOURCE", "merge_request_event"),
+        ]);
+        assert_eq!(
+            GitlabCi.run(&std_env),
+            CiRun {
+                build_number: "7".to_string(),
+                pipeline_id: "987654".to_string(),
+                job_url: "https://gitlab.com/group/project/-/jobs/123".to_string(),
+                runner: "shared-runner".to_string(),
+                trigger: "merge_request_event".to_string(),
+            }
+        );
+
+        let std_env = env_map(&[
+            ("TF_BUILD", "True"),
+            ("BUILD_BUILDID", "88"),
+            ("BUILD_BUILDNUMBER", "20261017.1"),
+            ("SYSTEM_COLLECTIONURI", "https://dev.azure.com/org/"),
+            ("SYSTEM_TEAMPROJECT", "project"),
+        ]);
+        let run = AzurePipelinesCi.run(&std_env);
+        assert_eq!(run.build_number, "20261017.1");
+        assert_eq!(
+            run.job_url,
+            "https://dev.azure.com/org/project/_build/results?buildId=88"
+        );
+        assert_eq!(run.trigger, "");
+
+        let std_env = env_map(&[
+            ("DRONE", "true"),
+            ("DRONE_BUILD_NUMBER", "12"),
+            (
+                "DRONE_BUILD_LINK",
+                "https://drone.example.com/group/project/12",
+            ),
+            ("DRONE_RUNNER_HOSTNAME", "runner-1"),
+            ("DRONE_BUILD_EVENT", "push"),
+        ]);
+        assert_eq!(
+            DroneCi.run(&std_env),
+            CiRun {
+                build_number: "12".to_string(),
+                pipeline_id: "".to_string(),
+                job_url: "https://drone.example.com/group/project/12".to_string(),
+                runner: "runner-1".to_string(),
+                trigger: "push".to_string(),
+            }
+        );
+
+        let std_env = env_map(&[
+            ("CI", "woodpecker"),
+            ("CI_PIPELINE_NUMBER", "8"),
+            (
+                "CI_PIPELINE_URL",
+                "https://ci.example.com/repos/3/pipeline/8",
+            ),
+            ("CI_PIPELINE_EVENT", "tag"),
+        ]);
+        assert_eq!(
+            WoodpeckerCi.run(&std_env),
+            CiRun {
+                build_number: "8".to_string(),
+                pipeline_id: "".to_string(),
+                job_url: "https://ci.example.com/repos/3/pipeline/8".to_string(),
+                runner: "".to_string(),
+                trigger: "tag".to_string(),
+            }
+        );
+
+        let github_run = CiRun {
+            build_number: "5".to_string(),
+            pipeline_id: "321".to_string(),
+            job_url: "https://git.example.com/group/project/actions/runs/321".to_string(),
+            runner: "runner-2".to_string(),
+            trigger: "push".to_string(),
+        };
+        let github_env = [
+            ("GITHUB_RUN_ID", "321"),
+            ("GITHUB_RUN_NUMBER", "5"),
+            ("GITHUB_SERVER_URL", "https://git.example.com"),
+            ("GITHUB_REPOSITORY", "group/project"),
+            ("GITHUB_EVENT_NAME", "push"),
+            ("RUNNER_NAME", "runner-2"),
+        ];
+        let std_env = env_map(&[&[("GITEA_ACTIONS", "true")], &github_env[..]].concat());
+        assert_eq!(GiteaCi.run(&std_env), github_run);
+        let std_env = env_map(&[&[("FORGEJO_ACTIONS", "true")], &github_env[..]].concat());
+        assert_eq!(ForgejoCi.run(&std_env), github_run);
+
+        let std_env = env_map(&[
+            ("JENKINS_URL", "https://jenkins.example.com/"),
+            ("BUILD_NUMBER", "31"),
+            ("BUILD_TAG", "jenkins-project-31"),
+            ("BUILD_URL", "https://jenkins.example.com/job/project/31/"),
+            ("NODE_NAME", "agent-1"),
+        ]);
+        assert_eq!(
+            JenkinsCi.run(&std_env),
+            CiRun {
+                build_number: "31".to_string(),
+                pipeline_id: "jenkins-project-31".to_string(),
+                job_url: "https://jenkins.example.com/job/project/31/".to_string(),
+                runner: "agent-1".to_string(),
+                trigger: "".to_string(),
+            }
+        );
+
+        let std_env = env_map(&[
+            ("TRAVIS", "true"),
+            ("TRAVIS_BUILD_NUMBER", "101"),
+            ("TRAVIS_BUILD_ID", "555"),
+            (
+                "TRAVIS_JOB_WEB_URL",
+                "https://app.travis-ci.com/group/project/jobs/556",
+            ),
+            ("TRAVIS_EVENT_TYPE", "pull_request"),
+        ]);
+        assert_eq!(
+            TravisCi.run(&std_env),
+            CiRun {
+                build_number: "101".to_string(),
+                pipeline_id: "555".to_string(),
+                job_url: "https://app.travis-ci.com/group/project/jobs/556".to_string(),
+                runner: "".to_string(),
+                trigger: "pull_request".to_string(),
+            }
+        );
+
+        let std_env = env_map(&[
+            ("CIRCLECI", "true"),
+            ("CIRCLE_BUILD_NUM", "64"),
+            ("CIRCLE_WORKFLOW_ID", "0b1c2d3e"),
+            (
+                "CIRCLE_BUILD_URL",
+                "https://circleci.com/gh/group/project/64",
+            ),
+        ]);
+        assert_eq!(
+            CircleCi.run(&std_env),
+            CiRun {
+                build_number: "64".to_string(),
+                pipeline_id: "0b1c2d3e".to_string(),
+                job_url: "https://circleci.com/gh/group/project/64".to_string(),
+                runner: "".to_string(),
+                trigger: "".to_string(),
+            }
+        );
+
+        let std_env = env_map(&[
+            ("BITBUCKET_BUILD_NUMBER", "17"),
+            ("BITBUCKET_PIPELINE_UUID", "{a1b2c3}"),
+            (
+                "BITBUCKET_GIT_HTTP_ORIGIN",
+                "https://bitbucket.org/group/project",
+            ),
+        ]);
+        assert_eq!(
+            BitbucketCi.run(&std_env),
+            CiRun {
+                build_number: "17".to_string(),
+                pipeline_id: "{a1b2c3}".to_string(),
+                job_url: "https://bitbucket.org/group/project/pipelines/results/17".to_string(),
+                runner: "".to_string(),
+                trigger: "".to_string(),
+            }
+        );
+
+        let std_env = env_map(&[
+            ("BUILDKITE", "true"),
+            ("BUILDKITE_BUILD_NUMBER", "9"),
+            ("BUILDKITE_BUILD_ID", "0190-abcd"),
+            (
+                "BUILDKITE_BUILD_URL",
+                "https://buildkite.com/org/pipeline/builds/9",
+            ),
+            ("BUILDKITE_JOB_ID", "0190-ef01"),
+            ("BUILDKITE_AGENT_NAME", "agent-3"),
+            ("BUILDKITE_SOURCE", "webhook"),
+        ]);
+        assert_eq!(
+            BuildkiteCi.run(&std_env),
+            CiRun {
+                build_number: "9".to_string(),
+                pipeline_id: "0190-abcd".to_string(),
+                job_url: "https://buildkite.com/org/pipeline/builds/9#0190-ef01".to_string(),
+                runner: "agent-3".to_string(),
+                trigger: "webhook".to_string(),
+            }
+        );
+
+        let std_env = env_map(&[("TEAMCITY_VERSION", "2025.03"), ("BUILD_NUMBER", "77")]);
+        assert_eq!(
+            TeamCityCi.run(&std_env),
+            CiRun {
+                build_number: "77".to_string(),
+                ..Default::default()
+            }
+        );
+
+        let std_env = env_map(&[
+            ("APPVEYOR", "True"),
+            ("APPVEYOR_BUILD_NUMBER", "23"),
+            ("APPVEYOR_BUILD_ID", "4455"),
+            ("APPVEYOR_URL", "https://ci.appveyor.com"),
+            ("APPVEYOR_ACCOUNT_NAME", "account"),
+            ("APPVEYOR_PROJECT_SLUG", "project"),
+            ("APPVEYOR_JOB_ID", "abc123"),
+        ]);
+        assert_eq!(
+            AppVeyorCi.run(&std_env),
+            CiRun {
+                build_number: "23".to_string(),
+                pipeline_id: "4455".to_string(),
+                job_url: "https://ci.appveyor.com/project/account/project/build/job/abc123"
+                    .to_string(),
+                runner: "".to_string(),
+                trigger: "".to_string(),
+            }
+        );
     }
 }
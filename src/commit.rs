@@ -0,0 +1,118 @@
+/// A commit message split into its subject, body and trailers.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct CommitMessage {
+    /// The first paragraph, joined into a single line like `git log --pretty=format:%s`.
+    pub subject: String,
+    /// The paragraphs between the subject and the trailers.
+    pub body: String,
+    /// The `Key: value` lines of the last paragraph, such as `Signed-off-by` and `Change-Id`.
+    pub trailers: Vec<(String, String)>,
+}
+
+impl CommitMessage {
+    /// Parses a raw commit message as printed by `git log --pretty=format:%B`.
+    ///
+    /// Like `git interpret-trailers`, the last paragraph is a trailer block if every line is a
+    /// `Key: value` trailer or a continuation line starting with whitespace.
+    pub fn parse(message: &str) -> Self {
+        let mut paragraphs: Vec<Vec<&str>> = vec![];
+        let mut paragraph = vec![];
+        for line in message.lines().map(str::trim_end) {
+            if line.trim().is_empty() {
+                if !paragraph.is_empty() {
+                    paragraphs.push(std::mem::take(&mut paragraph));
+                }
+            } else {
+                paragraph.push(line);
+            }
+        }
+        if !paragraph.is_empty() {
+            paragraphs.push(paragraph);
+        }
+
+        let mut paragraphs = paragraphs.into_iter();
+        let subject = paragraphs
+            .next()
+            .map(|x| x.iter().map(|x| x.trim()).collect::<Vec<_>>().join(" "))
+            .unwrap_or_default();
+        let mut paragraphs: Vec<Vec<&str>> = paragraphs.collect();
+        let trailers = paragraphs
+            .last()
+            .and_then(|x| parse_trailers(x))
+            .unwrap_or_default();
+        if !trailers.is_empty() {
+            paragraphs.pop();
+        }
+        let body = paragraphs
+            .iter()
+            .map(|x| x.join("\n"))
+            .collect::<Vec<_>>()
+            .join("\n\n");
+        CommitMessage {
+            subject,
+            body,
+            trailers,
+        }
+    }
+}
+
+/// Parses a paragraph made only of trailers, or returns `None`.
+fn parse_trailers(paragraph: &[&str]) -> Option<Vec<(String, String)>> {
+    let mut trailers: Vec<(String, String)> = vec![];
+    for line in paragraph {
+        if line.starts_with(char::is_whitespace) {
+            let (_, value) = trailers.last_mut()?;
+            value.push(' ');
+            value.push_str(line.trim());
+            continue;
+        }
+        let (key, value) = line.split_once(':')?;
+        if key.is_empty() || !key.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+            return None;
+        }
+        trailers.push((key.to_string(), value.trim().to_string()));
+    }
+    Some(trailers)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_commit_message() {
+        let message = "Fix the parser\nfor long lines\n\nThe parser failed on lines longer than\n80 characters.\n\nSee the issue for details.\n\nSigned-off-by: A U Thor <author@example.com>\nChange-Id: I8a4f5c2d\nCo-authored-by: Someone\n  Else <else@example.com>\n";
+        let message = CommitMessage::parse(message);
+        assert_eq!(message.subject, "Fix the parser for long lines");
+        assert_eq!(
+            message.body,
+            "The parser failed on lines longer than\n80 characters.\n\nSee the issue for details."
+        );
+        assert_eq!(
+            message.trailers,
+            [
+                ("Signed-off-by", "A U Thor <author@example.com>"),
+                ("Change-Id", "I8a4f5c2d"),
+                ("Co-authored-by", "Someone Else <else@example.com>"),
+            ]
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+        );
+    }
+
+    #[test]
+    fn test_parse_commit_message_without_trailers() {
+        let message = CommitMessage::parse("Subject only");
+        assert_eq!(message.subject, "Subject only");
+        assert!(message.body.is_empty());
+        assert!(message.trailers.is_empty());
+
+        // the subject is never a trailer block
+        let message =
+            CommitMessage::parse("fix: typo\n\nNote: not a trailer\nbecause of this line");
+        assert_eq!(message.subject, "fix: typo");
+        assert_eq!(message.body, "Note: not a trailer\nbecause of this line");
+        assert!(message.trailers.is_empty());
+
+        assert_eq!(CommitMessage::parse(""), CommitMessage::default());
+    }
+}
@@ -1,5 +1,6 @@
 use crate::{Format, SdResult, ShadowError};
 
+#[derive(Clone)]
 pub struct DateTime(jiff::Zoned);
 
 pub(crate) const DEFINE_SOURCE_DATE_EPOCH: &str = "SOURCE_DATE_EPOCH";
@@ -49,6 +50,16 @@ impl DateTime {
         Ok(DateTime::new(zoned))
     }
 
+    /// Creates a `DateTime` from a Unix timestamp in the fixed timezone `offset_seconds` east of UTC,
+    /// falling back to UTC if the offset is out of range.
+    pub fn timestamp_with_offset(time_stamp: i64, offset_seconds: i32) -> SdResult<Self> {
+        let utc_time = jiff::Timestamp::from_second(time_stamp).map_err(ShadowError::new)?;
+        let tz = jiff::tz::Offset::from_seconds(offset_seconds)
+            .map(jiff::tz::TimeZone::fixed)
+            .unwrap_or(jiff::tz::TimeZone::UTC);
+        Ok(DateTime::new(utc_time.to_zoned(tz)))
+    }
+
     pub fn from_iso8601_string(iso_string: &str) -> SdResult<Self> {
         let pieces = jiff::fmt::temporal::Pieces::parse(iso_string).map_err(ShadowError::new)?;
 
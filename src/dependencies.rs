@@ -0,0 +1,251 @@
+use crate::build::{str_tuples, ConstVal, ShadowConst};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const DEPENDENCIES_DOC: &str = r#"
+The packages locked in the `Cargo.lock` of the workspace, as `(name, version, source)` tuples in the order
+of `Cargo.lock`, i.e. sorted by name and version, e.g. `("serde", "1.0.219", "crates.io")`.
+The source is only the kind of the source: `crates.io`, `registry` for any other registry, `git`,
+or `path` for the workspace members and path dependencies. The URLs of registries and Git repositories
+are never included, so private sources are not revealed.
+`Cargo.lock` is parsed directly, without running Cargo or touching the network, and lists the packages
+of the whole workspace for all targets.
+Use the generated `dependency_version(name)` function to look up a single dependency.
+This constant will be empty if no `Cargo.lock` is found."#;
+pub const DEPENDENCIES: ShadowConst = "DEPENDENCIES";
+
+const DEPENDENCY_CHECKSUMS_DOC: &str = r#"
+The checksums of the packages locked in `Cargo.lock`, as `(name, version, checksum)` tuples in the order
+of [`DEPENDENCIES`], e.g. `("serde", "1.0.219", "5f0e2c6e...")`. The checksum is the SHA-256 of the `.crate`
+file of a registry package, and empty for Git and path packages, and for a `Cargo.lock` of version 1.
+This constant is disabled by default, see [`default_deny`](crate::default_deny)."#;
+pub const DEPENDENCY_CHECKSUMS: ShadowConst = "DEPENDENCY_CHECKSUMS";
+
+/// Where the dependency constants are read from, see [`ShadowBuilder::dependency_source`](crate::ShadowBuilder::dependency_source).
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum DependencySource {
+    /// Run `cargo tree` for [`CARGO_TREE`](crate::CARGO_TREE), besides reading `Cargo.lock` for [`DEPENDENCIES`].
+    #[default]
+    CargoTree,
+    /// Only read `Cargo.lock` for [`DEPENDENCIES`], without running `cargo tree`, which is slow and may
+    /// access the network. [`CARGO_TREE`](crate::CARGO_TREE) is left empty.
+    CargoLock,
+}
+
+/// A package locked in `Cargo.lock`, as emitted in [`DEPENDENCIES`] and [`DEPENDENCY_CHECKSUMS`].
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct LockedPackage {
+    pub name: String,
+    pub version: String,
+    /// The kind of the source: `crates.io`, `registry`, `git` or `path`.
+    pub source: String,
+    pub checksum: String,
+}
+
+impl LockedPackage {
+    /// Parses the `[[package]]` tables of a `Cargo.lock`, keeping their order.
+    ///
+    /// ```text
+    /// [[package]]
+    /// name = "serde"
+    /// version = "1.0.219"
+    /// source = "registry+https://github.com/rust-lang/crates.io-index"
+    /// checksum = "5f0e2c6ed6606019b4e29e69dbaba95b11854410e5347d525002456dbbb786b6"
+    /// ```
+    pub fn parse(lock: &str) -> Vec<Self> {
+        let mut packages = vec![];
+        let mut package: Option<LockedPackage> = None;
+        for line in lock.lines().map(str::trim) {
+            if line.starts_with('[') {
+                packages.extend(package.take());
+                if line == "[[package]]" {
+                    package = Some(LockedPackage {
+                        source: source_kind("").to_string(),
+                        ..Default::default()
+                    });
+                }
+                continue;
+            }
+            let (Some(package), Some((key, value))) = (package.as_mut(), line.split_once('='))
+            else {
+                continue;
+            };
+            let value = value.trim().trim_matches('"');
+            match key.trim() {
+                "name" => package.name = value.to_string(),
+                "version" => package.version = value.to_string(),
+                "source" => package.source = source_kind(value).to_string(),
+                "checksum" => package.checksum = value.to_string(),
+                _ => {}
+            }
+        }
+        packages.extend(package);
+        packages
+    }
+}
+
+/// The kind of the `source` of a locked package, without its URL.
+/// A package without a source is a workspace member or a path dependency.
+fn source_kind(source: &str) -> &'static str {
+    match source {
+        "" => "path",
+        "registry+https://github.com/rust-lang/crates.io-index"
+        | "sparse+https://index.crates.io/" => "crates.io",
+        x if x.starts_with("git+") => "git",
+        _ => "registry",
+    }
+}
+
+/// The `Cargo.lock` of the workspace that the package at `src_path` belongs to.
+/// Cargo writes it next to the manifest of the workspace root, so a `Cargo.lock` left next to
+/// a member, e.g. from before it joined the workspace, is not the one used for the build.
+pub(crate) fn cargo_lock_path(src_path: &Path) -> Option<PathBuf> {
+    let root = workspace_root(src_path).unwrap_or(src_path);
+    Some(root.join("Cargo.lock")).filter(|x| x.is_file())
+}
+
+/// The workspace root of the package at `src_path`: the nearest directory from `src_path` up
+/// whose `Cargo.toml` has a `[workspace]` table, or `None` for a package outside any workspace.
+fn workspace_root(src_path: &Path) -> Option<&Path> {
+    src_path.ancestors().find(|dir| {
+        fs::read_to_string(dir.join("Cargo.toml")).is_ok_and(|manifest| {
+            manifest
+                .lines()
+                .map(str::trim)
+                .any(|x| x == "[workspace]" || x.starts_with("[workspace."))
+        })
+    })
+}
+
+pub(crate) fn new_dependencies(cargo_lock: Option<&Path>) -> BTreeMap<ShadowConst, ConstVal> {
+    let packages = cargo_lock
+        .and_then(|x| fs::read_to_string(x).ok())
+        .map(|x| LockedPackage::parse(&x))
+        .unwrap_or_default();
+
+    let mut map = BTreeMap::new();
+    let rows: Vec<[&str; 3]> = packages
+        .iter()
+        .map(|x| [&*x.name, &*x.version, &*x.source])
+        .collect();
+    let mut val = ConstVal::new_tuples(DEPENDENCIES_DOC, 3);
+    val.v = str_tuples(&rows);
+    map.insert(DEPENDENCIES, val);
+
+    let rows: Vec<[&str; 3]> = packages
+        .iter()
+        .map(|x| [&*x.name, &*x.version, &*x.checksum])
+        .collect();
+    let mut val = ConstVal::new_tuples(DEPENDENCY_CHECKSUMS_DOC, 3);
+    val.v = str_tuples(&rows);
+    map.insert(DEPENDENCY_CHECKSUMS, val);
+    map
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CARGO_LOCK: &str = r#"# This file is automatically @generated by Cargo.
+# It is not intended for manual editing.
+version = 4
+
+[[package]]
+name = "demo"
+version = "0.1.0"
+dependencies = [
+ "private",
+ "serde 1.0.219",
+ "tool",
+]
+
+[[package]]
+name = "private"
+version = "0.3.1"
+source = "sparse+https://crates.example.com/index/"
+checksum = "0a1b"
+
+[[package]]
+name = "serde"
+version = "0.9.15"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+checksum = "34b6"
+
+[[package]]
+name = "serde"
+version = "1.0.219"
+source = "sparse+https://index.crates.io/"
+checksum = "5f0e"
+
+[[package]]
+name = "tool"
+version = "0.2.0"
+source = "git+ssh://git@git.example.com/team/tool.git?branch=main#1c2d3e4f"
+
+[metadata]
+"#;
+
+    #[test]
+    fn test_parse_cargo_lock() {
+        let packages = LockedPackage::parse(CARGO_LOCK);
+        let rows: Vec<_> = packages
+            .iter()
+            .map(|x| (&*x.name, &*x.version, &*x.source, &*x.checksum))
+            .collect();
+        assert_eq!(
+            rows,
+            [
+                ("demo", "0.1.0", "path", ""),
+                ("private", "0.3.1", "registry", "0a1b"),
+                ("serde", "0.9.15", "crates.io", "34b6"),
+                ("serde", "1.0.219", "crates.io", "5f0e"),
+                ("tool", "0.2.0", "git", ""),
+            ]
+        );
+        assert!(LockedPackage::parse("").is_empty());
+    }
+
+    #[test]
+    fn test_new_dependencies() {
+        let dir = std::env::temp_dir().join(format!("shadow-rs-lock-{}", std::process::id()));
+        let member = dir.join("crates").join("demo");
+        fs::create_dir_all(&member).unwrap();
+        fs::write(
+            dir.join("Cargo.toml"),
+            "[workspace]\nmembers = [\"crates/*\"]\n",
+        )
+        .unwrap();
+        fs::write(dir.join("Cargo.lock"), CARGO_LOCK).unwrap();
+        fs::write(member.join("Cargo.toml"), "[package]\nname = \"demo\"\n").unwrap();
+        // a stale lock file of the member is not the one of the workspace
+        fs::write(member.join("Cargo.lock"), "").unwrap();
+
+        let cargo_lock = cargo_lock_path(&member);
+        assert_eq!(cargo_lock, Some(dir.join("Cargo.lock")));
+
+        // a package outside any workspace has its own lock file
+        let package = dir.join("standalone");
+        fs::create_dir_all(&package).unwrap();
+        fs::write(dir.join("Cargo.toml"), "[package]\nname = \"root\"\n").unwrap();
+        assert_eq!(cargo_lock_path(&package), None);
+        fs::write(package.join("Cargo.lock"), CARGO_LOCK).unwrap();
+        assert_eq!(cargo_lock_path(&package), Some(package.join("Cargo.lock")));
+
+        let map = new_dependencies(cargo_lock.as_deref());
+        let dependencies = map.get(DEPENDENCIES).unwrap();
+        assert!(dependencies.v.starts_with(
+            r#"[("demo", "0.1.0", "path"), ("private", "0.3.1", "registry"), ("serde", "0.9.15", "crates.io")"#
+        ));
+        assert!(!dependencies.v.contains("example.com"));
+        assert!(map
+            .get(DEPENDENCY_CHECKSUMS)
+            .unwrap()
+            .v
+            .contains(r#"("serde", "1.0.219", "5f0e")"#));
+        fs::remove_dir_all(&dir).unwrap();
+
+        let map = new_dependencies(None);
+        assert_eq!(map.get(DEPENDENCIES).unwrap().v, "[]");
+    }
+}
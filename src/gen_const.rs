@@ -1,7 +1,8 @@
 #[allow(unused_imports)]
 use crate::CARGO_CLIPPY_ALLOW_ALL;
 #[cfg(feature = "metadata")]
-use crate::{Shadow, CARGO_METADATA};
+use crate::CARGO_METADATA;
+use crate::{Shadow, DEPENDENCIES, DEPENDENCY_CHECKSUMS, GIT_SUBMODULES};
 
 macro_rules! gen_const {
     ($fn_name:ident, $fn_body:expr) => {
@@ -79,6 +80,87 @@ gen_const!(clap_long_version_tag_const, CLAP_LONG_VERSION_TAG_CONST);
 pub(crate) const BUILD_CONST_VERSION: &str = "VERSION";
 pub(crate) const BUILD_CONST_CLAP_LONG_VERSION: &str = "CLAP_LONG_VERSION";
 
+pub(crate) fn git_submodule_fn(shadow: &Shadow) -> String {
+    if !shadow.map.contains_key(GIT_SUBMODULES) {
+        return "".to_string();
+    }
+    format!(
+        r#"
+/// Looks up the Git submodule at the repository-relative `path` in the generated constant GIT_SUBMODULES.
+///
+/// # Return Values
+/// - `Some((commit, state))`: The commit pinned by the superproject and the state of the submodule,
+///   one of `clean`, `dirty`, `out-of-sync` or `uninitialized`.
+/// - `None`: There is no submodule at `path`.
+#[allow(dead_code)]
+{CARGO_CLIPPY_ALLOW_ALL}
+pub fn git_submodule(path: &str) -> Option<(&'static str, &'static str)> {{
+    GIT_SUBMODULES
+        .iter()
+        .find(|(p, _, _)| *p == path)
+        .map(|(_, commit, state)| (*commit, *state))
+}}"#
+    )
+}
+
+pub(crate) fn dependency_fn(shadow: &Shadow) -> String {
+    if !shadow.map.contains_key(DEPENDENCIES) {
+        return "".to_string();
+    }
+    let mut fns = format!(
+        r#"
+/// Looks up the package `name` in the generated constant DEPENDENCIES, read from `Cargo.lock`.
+///
+/// # Return Values
+/// - `Some(version)`: The locked version of the package, the highest one if several versions are locked.
+/// - `None`: The package is not in `Cargo.lock`.
+#[allow(dead_code)]
+{CARGO_CLIPPY_ALLOW_ALL}
+pub fn dependency_version(name: &str) -> Option<&'static str> {{
+    dependency_versions(name).last()
+}}
+
+/// Returns the locked versions of the package `name` in the generated constant DEPENDENCIES,
+/// from the lowest to the highest, with the kind of their source: `crates.io`, `registry`, `git` or `path`.
+#[allow(dead_code)]
+{CARGO_CLIPPY_ALLOW_ALL}
+pub fn dependency_sources(name: &str) -> impl Iterator<Item = (&'static str, &'static str)> + '_ {{
+    DEPENDENCIES
+        .iter()
+        .filter(move |(n, _, _)| *n == name)
+        .map(|(_, version, source)| (*version, *source))
+}}
+
+/// Returns the locked versions of the package `name` in the generated constant DEPENDENCIES,
+/// from the lowest to the highest.
+#[allow(dead_code)]
+{CARGO_CLIPPY_ALLOW_ALL}
+pub fn dependency_versions(name: &str) -> impl Iterator<Item = &'static str> + '_ {{
+    dependency_sources(name).map(|(version, _)| version)
+}}"#
+    );
+    if shadow.map.contains_key(DEPENDENCY_CHECKSUMS) {
+        fns.push_str(&format!(
+            r#"
+
+/// Looks up the checksum of the package `name` at `version` in the generated constant DEPENDENCY_CHECKSUMS.
+///
+/// # Return Values
+/// - `Some(checksum)`: The SHA-256 of the `.crate` file, empty for Git and path packages.
+/// - `None`: The package is not in `Cargo.lock`.
+#[allow(dead_code)]
+{CARGO_CLIPPY_ALLOW_ALL}
+pub fn dependency_checksum(name: &str, version: &str) -> Option<&'static str> {{
+    DEPENDENCY_CHECKSUMS
+        .iter()
+        .find(|(n, v, _)| *n == name && *v == version)
+        .map(|(_, _, checksum)| *checksum)
+}}"#
+        ));
+    }
+    fns
+}
+
 #[cfg(feature = "metadata")]
 #[allow(dead_code)]
 pub(crate) fn cargo_metadata_fn(shadow: &Shadow) -> String {
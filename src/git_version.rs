@@ -0,0 +1,190 @@
+use std::fmt::{Display, Formatter};
+
+/// The format of [`GIT_SEMVER`](crate::GIT_SEMVER).
+///
+/// With the last tag `v1.3.0`, 7 commits since it, the short hash `1a2b3c4` and a dirty working tree,
+/// the schemes produce:
+///
+/// | Scheme                     | Version                       |
+/// |----------------------------|-------------------------------|
+/// | [`VersionScheme::Semver`]  | `1.3.1-dev.7+g1a2b3c4.dirty`  |
+/// | [`VersionScheme::Pep440`]  | `1.3.1.dev7+g1a2b3c4.dirty`   |
+/// | [`VersionScheme::Debian`]  | `1.3.1~dev.7+g1a2b3c4.dirty`  |
+///
+/// A build exactly at a tag with a clean working tree yields the tag version itself, e.g. `1.3.0`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum VersionScheme {
+    /// [Semantic Versioning](https://semver.org): commits since the tag are a `dev.N` pre-release of the next patch version,
+    /// the hash and dirty flag are build metadata.
+    #[default]
+    Semver,
+    /// Like [PEP 440](https://peps.python.org/pep-0440/): commits since the tag are a `.devN` release,
+    /// the hash and dirty flag are a local version label.
+    Pep440,
+    /// Like Debian upstream versions: pre-releases follow a `~`, so that they sort before the release.
+    Debian,
+}
+
+/// A version derived from the last Git tag, as emitted in [`GIT_SEMVER`](crate::GIT_SEMVER).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GitVersion {
+    pub major: u64,
+    pub minor: u64,
+    pub patch: u64,
+    /// The pre-release identifiers of the tag, e.g. `rc.1`.
+    pub tag_pre: String,
+    /// The number of commits since the tag, if there are any.
+    pub dev: Option<usize>,
+    /// The abbreviated commit hash, only set for builds that are not exactly a tagged, clean commit.
+    pub hash: Option<String>,
+    pub dirty: bool,
+    pub scheme: VersionScheme,
+}
+
+impl GitVersion {
+    /// Derives the version from the last tag, e.g. `v1.3.0` or `1.4.0-rc.1`.
+    /// Returns `None` if the tag is not a version.
+    ///
+    /// Commits since a release tag make a pre-release of the next patch version,
+    /// commits since a pre-release tag extend its pre-release.
+    pub fn from_describe(
+        tag: &str,
+        commits_since_tag: usize,
+        short_hash: &str,
+        dirty: bool,
+        scheme: VersionScheme,
+    ) -> Option<Self> {
+        let tag = tag.strip_prefix(['v', 'V']).unwrap_or(tag);
+        let tag = tag.split_once('+').map_or(tag, |(x, _)| x);
+        let (release, tag_pre) = tag.split_once('-').unwrap_or((tag, ""));
+        let mut numbers = release.split('.').map(|x| x.parse::<u64>().ok());
+        let major = numbers.next()??;
+        let minor = numbers.next().unwrap_or(Some(0))?;
+        let mut patch = numbers.next().unwrap_or(Some(0))?;
+        if numbers.next().is_some() {
+            return None;
+        }
+
+        let dev = (commits_since_tag > 0).then_some(commits_since_tag);
+        if dev.is_some() && tag_pre.is_empty() {
+            patch += 1;
+        }
+        let hash = (dev.is_some() || dirty)
+            .then(|| short_hash.to_string())
+            .filter(|x| !x.is_empty());
+        Some(GitVersion {
+            major,
+            minor,
+            patch,
+            tag_pre: tag_pre.to_string(),
+            dev,
+            hash,
+            dirty,
+            scheme,
+        })
+    }
+
+    /// The semver pre-release identifiers, e.g. `dev.7` or `rc.1.dev.7`.
+    pub fn pre(&self) -> String {
+        let dev = self.dev.map(|x| format!("dev.{x}"));
+        [Some(self.tag_pre.clone()).filter(|x| !x.is_empty()), dev]
+            .into_iter()
+            .flatten()
+            .collect::<Vec<_>>()
+            .join(".")
+    }
+
+    /// The build metadata, e.g. `g1a2b3c4.dirty`.
+    pub fn build(&self) -> String {
+        let hash = self.hash.as_ref().map(|x| format!("g{x}"));
+        let dirty = self.dirty.then(|| "dirty".to_string());
+        [hash, dirty]
+            .into_iter()
+            .flatten()
+            .collect::<Vec<_>>()
+            .join(".")
+    }
+}
+
+impl Display for GitVersion {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)?;
+        match self.scheme {
+            VersionScheme::Semver | VersionScheme::Debian => {
+                let pre = self.pre();
+                if !pre.is_empty() {
+                    let separator = match self.scheme {
+                        VersionScheme::Debian => '~',
+                        _ => '-',
+                    };
+                    write!(f, "{separator}{pre}")?;
+                }
+            }
+            VersionScheme::Pep440 => {
+                write!(f, "{}", self.tag_pre.replace(['.', '-'], ""))?;
+                if let Some(x) = self.dev {
+                    write!(f, ".dev{x}")?;
+                }
+            }
+        }
+        let build = self.build();
+        if !build.is_empty() {
+            write!(f, "+{build}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn version(tag: &str, commits: usize, dirty: bool, scheme: VersionScheme) -> String {
+        GitVersion::from_describe(tag, commits, "1a2b3c4", dirty, scheme)
+            .map(|x| x.to_string())
+            .unwrap_or_default()
+    }
+
+    #[test]
+    fn test_git_version() {
+        use VersionScheme::*;
+        assert_eq!(version("v1.3.0", 0, false, Semver), "1.3.0");
+        assert_eq!(version("v1.3.0", 0, true, Semver), "1.3.0+g1a2b3c4.dirty");
+        assert_eq!(version("v1.3.0", 7, false, Semver), "1.3.1-dev.7+g1a2b3c4");
+        assert_eq!(
+            version("v1.3.0", 7, true, Semver),
+            "1.3.1-dev.7+g1a2b3c4.dirty"
+        );
+        assert_eq!(version("1.4.0-rc.1", 0, false, Semver), "1.4.0-rc.1");
+        assert_eq!(
+            version("1.4.0-rc.1", 2, false, Semver),
+            "1.4.0-rc.1.dev.2+g1a2b3c4"
+        );
+        assert_eq!(version("1.4", 0, false, Semver), "1.4.0");
+
+        assert_eq!(
+            version("v1.3.0", 7, true, Pep440),
+            "1.3.1.dev7+g1a2b3c4.dirty"
+        );
+        assert_eq!(
+            version("1.4.0-rc.1", 2, false, Pep440),
+            "1.4.0rc1.dev2+g1a2b3c4"
+        );
+        assert_eq!(version("v1.3.0", 0, false, Pep440), "1.3.0");
+
+        assert_eq!(
+            version("v1.3.0", 7, true, Debian),
+            "1.3.1~dev.7+g1a2b3c4.dirty"
+        );
+        assert_eq!(version("1.4.0-rc.1", 0, false, Debian), "1.4.0~rc.1");
+
+        assert_eq!(version("nightly", 3, false, Semver), "");
+        assert_eq!(version("1.2.3.4", 3, false, Semver), "");
+        assert_eq!(version("", 3, false, Semver), "");
+
+        let v = GitVersion::from_describe("v1.3.0", 7, "1a2b3c4", true, Semver).unwrap();
+        assert_eq!((v.major, v.minor, v.patch), (1, 3, 1));
+        assert_eq!(v.pre(), "dev.7");
+        assert_eq!(v.build(), "g1a2b3c4.dirty");
+    }
+}
@@ -83,6 +83,7 @@
 //!     println!("{}", build::CARGO_VERSION);//cargo 1.45.0 (744bd1fbb 2020-06-15)
 //!     println!("{}", build::PKG_VERSION);//0.3.13
 //!     println!("{}", build::CARGO_TREE); //like command:cargo tree
+//!     println!("{:?}", build::dependency_version("serde")); // Some("1.0.219"), read from Cargo.lock
 //!     println!("{}", build::CARGO_MANIFEST_DIR); // /User/baoyachi/shadow-rs/ |
 //!
 //!     println!("{}", build::PROJECT_NAME);//shadow-rs
@@ -160,8 +161,12 @@ mod build;
 #[cfg(feature = "build")]
 mod ci;
 #[cfg(feature = "build")]
+mod commit;
+#[cfg(feature = "build")]
 mod date_time;
 #[cfg(feature = "build")]
+mod dependencies;
+#[cfg(feature = "build")]
 mod env;
 #[cfg(feature = "build")]
 mod err;
@@ -170,9 +175,19 @@ mod gen_const;
 #[cfg(feature = "build")]
 mod git;
 #[cfg(feature = "build")]
+mod git_version;
+#[cfg(feature = "build")]
 mod hook;
 #[cfg(feature = "build")]
 mod shadow;
+#[cfg(feature = "build")]
+mod signature;
+#[cfg(feature = "build")]
+mod status;
+#[cfg(feature = "build")]
+mod tag;
+#[cfg(feature = "build")]
+mod vcs;
 
 /// Re-exported from the const_format crate
 pub use const_format::*;
@@ -185,7 +200,20 @@ mod pub_export {
     pub use crate::date_time::DateTime;
     pub use crate::err::{SdResult, ShadowError};
     pub use crate::shadow::Shadow;
-    pub use {crate::build::default_deny, crate::build::ShadowConst, crate::env::*, crate::git::*};
+    pub use {
+        crate::build::default_deny,
+        crate::build::ShadowConst,
+        crate::ci::*,
+        crate::commit::CommitMessage,
+        crate::dependencies::*,
+        crate::env::*,
+        crate::git::*,
+        crate::git_version::*,
+        crate::signature::*,
+        crate::status::*,
+        crate::tag::{check_tag_version, AnnotatedTag, TagCheck, TagPattern},
+        crate::vcs::*,
+    };
 
     pub trait Format {
         fn human_format(&self) -> String;
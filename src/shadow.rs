@@ -1,14 +1,16 @@
 use crate::build::{ConstType, ConstVal};
-use crate::ci::CiType;
+use crate::ci::{detect_ci, new_ci, CiProvider};
 use crate::date_time::now_date_time;
+use crate::dependencies::{cargo_lock_path, new_dependencies};
 use crate::env::{new_project, new_system_env};
 use crate::gen_const::{
-    clap_long_version_branch_const, clap_long_version_tag_const, version_branch_const,
-    version_tag_const, BUILD_CONST_CLAP_LONG_VERSION, BUILD_CONST_VERSION,
+    clap_long_version_branch_const, clap_long_version_tag_const, dependency_fn, git_submodule_fn,
+    version_branch_const, version_tag_const, BUILD_CONST_CLAP_LONG_VERSION, BUILD_CONST_VERSION,
 };
 use crate::git::new_git;
 use crate::{
-    get_std_env, BuildPattern, SdResult, ShadowBuilder, ShadowConst, CARGO_CLIPPY_ALLOW_ALL, TAG,
+    get_std_env, BuildPattern, DependencySource, SdResult, ShadowBuilder, ShadowConst,
+    CARGO_CLIPPY_ALLOW_ALL, DEPENDENCIES, TAG,
 };
 use std::collections::{BTreeMap, BTreeSet};
 use std::fs::File;
@@ -79,6 +81,11 @@ pub struct Shadow {
     /// This field sets the pattern for how often the package should be rebuilt. Options include `Lazy`, `RealTime`, and `Custom`, each with its own implications on the build frequency and conditions under which a rebuild is triggered.
     /// It can be configured using [`ShadowBuilder::build_pattern`].
     pub build_pattern: BuildPattern,
+
+    /// Whether `cargo tree` is run for `CARGO_TREE`, or only `Cargo.lock` is read.
+    ///
+    /// It can be configured using [`ShadowBuilder::dependency_source`].
+    pub dependency_source: DependencySource,
 }
 
 impl Shadow {
@@ -94,23 +101,10 @@ impl Shadow {
         Ok(())
     }
 
-    /// Try to infer the CI system that we're currently running under.
-    ///
-    /// TODO: Recognize other CI types, especially Travis and Jenkins.
-    fn try_ci(&self) -> CiType {
-        if let Some(c) = self.std_env.get("GITLAB_CI") {
-            if c == "true" {
-                return CiType::Gitlab;
-            }
-        }
-
-        if let Some(c) = self.std_env.get("GITHUB_ACTIONS") {
-            if c == "true" {
-                return CiType::Github;
-            }
-        }
-
-        CiType::None
+    /// Try to infer the CI system that we're currently running under, from the providers of
+    /// `builder` followed by the built-in ones.
+    fn try_ci<'b>(&self, builder: &'b ShadowBuilder) -> Option<&'b dyn CiProvider> {
+        detect_ci(builder.get_ci_providers(), &self.std_env)
     }
 
     /// Checks if the specified build constant is in the deny list.
@@ -146,25 +140,39 @@ impl Shadow {
             deny_const,
             out_path: out_path.to_string(),
             build_pattern,
+            dependency_source: builder.get_dependency_source(),
         };
         shadow.std_env = get_std_env();
 
-        let ci_type = shadow.try_ci();
+        let ci = shadow.try_ci(&builder);
         let src_path = Path::new(src_path.as_str());
 
-        let mut map = new_git(src_path, ci_type, &shadow.std_env);
+        let mut map = new_git(src_path, ci, &shadow.std_env, &builder)?;
+        for (k, v) in new_ci(ci, &shadow.std_env) {
+            map.insert(k, v);
+        }
         for (k, v) in new_project(&shadow.std_env) {
             map.insert(k, v);
         }
         for (k, v) in new_system_env(&shadow) {
             map.insert(k, v);
         }
+        let cargo_lock = cargo_lock_path(src_path);
+        for (k, v) in new_dependencies(cargo_lock.as_deref()) {
+            map.insert(k, v);
+        }
         shadow.map = map;
 
         // deny const
         shadow.filter_deny();
 
         shadow.write_all()?;
+        shadow.build_pattern.rerun_if_git_changed(src_path);
+        if let Some(cargo_lock) = &cargo_lock {
+            if shadow.map.contains_key(DEPENDENCIES) {
+                shadow.build_pattern.rerun_if_lock_changed(cargo_lock);
+            }
+        }
 
         // handle hook
         if let Some(h) = builder.get_hook() {
@@ -263,6 +271,15 @@ impl Shadow {
                 ConstType::Int,
                 val.v.parse::<i64>().unwrap_or_default()
             ),
+            ConstType::StrTuples(_) | ConstType::StrSlice => format!(
+                "#[allow(dead_code)]\n\
+                {}\n\
+            pub const {} :{} = &{};",
+                CARGO_CLIPPY_ALLOW_ALL,
+                shadow_const.to_ascii_uppercase(),
+                val.t,
+                val.v
+            ),
         };
 
         writeln!(&self.f, "{desc}")?;
@@ -304,7 +321,7 @@ impl Shadow {
                         "\t\t", "\n"
                     )
                 }
-                ConstType::Slice => {
+                ConstType::Slice | ConstType::StrTuples(_) | ConstType::StrSlice => {
                     default.push_str(&format!("\t\t\t{k}: false,\n"));
                     all.push_str(&format!("\t\t\t{k}: true,\n"));
                     format!(
@@ -394,6 +411,9 @@ impl Shadow {
             }
         }
 
+        writeln!(&self.f, "{}", git_submodule_fn(self))?;
+        writeln!(&self.f, "{}", dependency_fn(self))?;
+
         Ok(())
     }
 }
@@ -428,6 +448,19 @@ mod tests {
         let expect = "pub const CARGO_TREE :&str";
         assert!(!content.contains(expect));
 
+        let shadow = ShadowBuilder::builder()
+            .src_path("./")
+            .out_path("./")
+            .dependency_source(DependencySource::CargoLock)
+            .build()?;
+        assert_eq!(shadow.map.get(CARGO_TREE).unwrap().v, "");
+        assert!(shadow
+            .map
+            .get(DEPENDENCIES)
+            .unwrap()
+            .v
+            .contains(r#"("shadow-rs", "#));
+
         Ok(())
     }
 }
@@ -0,0 +1,370 @@
+use std::fmt::{Display, Formatter};
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// The format of a commit or tag signature, detected from its armor header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignatureFormat {
+    /// An OpenPGP signature, e.g. made by `gpg`.
+    Gpg,
+    /// An SSH signature, made with `gpg.format=ssh`.
+    Ssh,
+    /// An X.509 (S/MIME) signature, made with `gpg.format=x509`.
+    X509,
+}
+
+impl SignatureFormat {
+    /// The format of the signature starting at the armor `line`, if it is one.
+    pub(crate) fn from_armor(line: &str) -> Option<Self> {
+        match line.trim() {
+            "-----BEGIN PGP SIGNATURE-----" => Some(SignatureFormat::Gpg),
+            "-----BEGIN SSH SIGNATURE-----" => Some(SignatureFormat::Ssh),
+            "-----BEGIN SIGNED MESSAGE-----" => Some(SignatureFormat::X509),
+            _ => None,
+        }
+    }
+}
+
+impl Display for SignatureFormat {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SignatureFormat::Gpg => write!(f, "gpg"),
+            SignatureFormat::Ssh => write!(f, "ssh"),
+            SignatureFormat::X509 => write!(f, "x509"),
+        }
+    }
+}
+
+/// The signature of a commit or tag, as reported in [`COMMIT_SIGNATURE`](crate::COMMIT_SIGNATURE)
+/// and [`TAG_SIGNATURE`](crate::TAG_SIGNATURE).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GitSignature {
+    pub format: SignatureFormat,
+    /// The fingerprint of the signing key, or the key id if only that is known.
+    /// Empty if the verifier did not report the key.
+    pub key: String,
+    /// Whether the signature verified against a locally known key.
+    pub verified: bool,
+}
+
+impl GitSignature {
+    /// Detects the signature of a raw commit or tag object, as printed by `git cat-file`.
+    ///
+    /// A commit carries its signature in the `gpgsig` header, or `gpgsig-sha256` in a SHA-256 repository,
+    /// a tag appends it to the message.
+    pub fn detect(kind: &str, object: &str) -> Option<SignatureFormat> {
+        match kind {
+            "commit" => object
+                .lines()
+                .take_while(|x| !x.is_empty())
+                .find_map(|x| {
+                    x.strip_prefix("gpgsig ")
+                        .or_else(|| x.strip_prefix("gpgsig-sha256 "))
+                })
+                .and_then(SignatureFormat::from_armor),
+            _ => object.lines().find_map(SignatureFormat::from_armor),
+        }
+    }
+
+    /// Splits a raw signed commit or tag object into the signed payload and the armored signature,
+    /// as Git passes them to the verifier.
+    ///
+    /// The payload of a commit is the object without its `gpgsig` and `gpgsig-sha256` headers,
+    /// the payload of a tag is the object up to the armor line of its signature.
+    pub fn split(kind: &str, object: &str) -> Option<(String, String)> {
+        if kind != "commit" {
+            let mut offset = 0;
+            for line in object.split_inclusive('\n') {
+                if SignatureFormat::from_armor(line).is_some() {
+                    return Some((object[..offset].to_string(), object[offset..].to_string()));
+                }
+                offset += line.len();
+            }
+            return None;
+        }
+
+        let (header, message) = object.split_once("\n\n").unwrap_or((object, ""));
+        let mut payload = String::new();
+        let mut signature = String::new();
+        let mut signatures = 0;
+        let mut in_signature = false;
+        for line in header.lines() {
+            let value = line
+                .strip_prefix("gpgsig ")
+                .or_else(|| line.strip_prefix("gpgsig-sha256 "));
+            if value.is_some() {
+                signatures += 1;
+                in_signature = true;
+            }
+            // the continuation lines of a header start with a space
+            match value.or_else(|| line.strip_prefix(' ').filter(|_| in_signature)) {
+                // only the first signature is verified, but all of them are left out of the payload
+                Some(x) if signatures == 1 => {
+                    signature.push_str(x);
+                    signature.push('\n');
+                }
+                Some(_) => {}
+                None => {
+                    in_signature = false;
+                    payload.push_str(line);
+                    payload.push('\n');
+                }
+            }
+        }
+        if signature.is_empty() {
+            return None;
+        }
+        payload.push('\n');
+        payload.push_str(message);
+        Some((payload, signature))
+    }
+
+    /// Reads the signing key from the output of `git verify-commit --raw` or `git verify-tag --raw`.
+    ///
+    /// GPG reports status lines like `[GNUPG:] VALIDSIG <fingerprint> ...`, where `ERRSIG` carries
+    /// the fingerprint last if the key is missing. SSH reports `Good "git" signature for <principal>
+    /// with ED25519 key SHA256:<fingerprint>`.
+    pub fn parse_key(output: &str) -> String {
+        let mut key_id = None;
+        for line in output.lines() {
+            let mut fields = line.split_whitespace();
+            if fields.next() == Some("[GNUPG:]") {
+                let status = fields.next().unwrap_or_default();
+                let fields: Vec<&str> = fields.collect();
+                match status {
+                    "VALIDSIG" => return fields.first().unwrap_or(&"").to_string(),
+                    "ERRSIG" => match fields.last() {
+                        Some(x) if fields.len() > 6 && x.len() >= 40 => return x.to_string(),
+                        _ => key_id = key_id.or(fields.first().copied()),
+                    },
+                    "GOODSIG" | "BADSIG" | "EXPSIG" | "EXPKEYSIG" | "REVKEYSIG" => {
+                        key_id = key_id.or(fields.first().copied())
+                    }
+                    _ => {}
+                }
+            } else if let Some((_, key)) = line.split_once(" key ") {
+                if key.starts_with("SHA256:") {
+                    return key
+                        .split_whitespace()
+                        .next()
+                        .unwrap_or_default()
+                        .to_string();
+                }
+            }
+        }
+        key_id.unwrap_or_default().to_string()
+    }
+}
+
+/// Verifies signatures without Git, running the programs that Git would run for `git verify-commit`:
+/// `gpg`, `gpgsm` for X.509 and `ssh-keygen` for SSH, unless configured otherwise.
+#[derive(Debug, Default, Clone)]
+pub struct SignatureVerifier {
+    /// `gpg.openpgp.program` or `gpg.program`.
+    pub gpg_program: Option<String>,
+    /// `gpg.x509.program`.
+    pub x509_program: Option<String>,
+    /// `gpg.ssh.program`.
+    pub ssh_program: Option<String>,
+    /// `gpg.ssh.allowedSignersFile`. Like Git, an SSH signature is not checked at all without it.
+    pub allowed_signers: Option<PathBuf>,
+}
+
+impl SignatureVerifier {
+    /// Verifies the armored `signature` of `payload`, as split by [`GitSignature::split`].
+    ///
+    /// Returns whether the signature verified, and the output of the verifier, from which
+    /// [`GitSignature::parse_key`] reads the key. A verifier that cannot be run verifies nothing.
+    pub fn verify(
+        &self,
+        format: SignatureFormat,
+        payload: &str,
+        signature: &str,
+    ) -> (bool, String) {
+        static COUNT: AtomicUsize = AtomicUsize::new(0);
+        let signature_file = std::env::temp_dir().join(format!(
+            "shadow-rs-{}-{}.sig",
+            std::process::id(),
+            COUNT.fetch_add(1, Ordering::Relaxed)
+        ));
+        if std::fs::write(&signature_file, signature).is_err() {
+            return (false, String::new());
+        }
+        let signature_path = signature_file.to_string_lossy().to_string();
+        let signature_path = signature_path.as_str();
+
+        let result = match format {
+            SignatureFormat::Gpg | SignatureFormat::X509 => {
+                let program = match format {
+                    SignatureFormat::Gpg => self.gpg_program.as_deref().unwrap_or("gpg"),
+                    _ => self.x509_program.as_deref().unwrap_or("gpgsm"),
+                };
+                let args = ["--status-fd=1", "--verify", signature_path, "-"];
+                run_verifier(program, &args, payload)
+                    .map(|(ok, output)| (ok && output.contains("[GNUPG:] GOODSIG "), output))
+            }
+            SignatureFormat::Ssh => self.allowed_signers.as_ref().and_then(|allowed_signers| {
+                let program = self.ssh_program.as_deref().unwrap_or("ssh-keygen");
+                let allowed_signers = allowed_signers.to_string_lossy().to_string();
+                let allowed_signers = allowed_signers.as_str();
+                // the principal that the allowed signers file names for the key of the signature
+                let args = [
+                    "-Y",
+                    "find-principals",
+                    "-f",
+                    allowed_signers,
+                    "-s",
+                    signature_path,
+                ];
+                let principal = run_verifier(program, &args, "")
+                    .filter(|(ok, _)| *ok)
+                    .and_then(|(_, output)| Some(output.lines().next()?.trim().to_string()))
+                    .filter(|x| !x.is_empty());
+                match principal {
+                    Some(principal) => {
+                        let args = ["-Y", "verify", "-f", allowed_signers, "-I", &principal];
+                        let args = [&args[..], &["-n", "git", "-s", signature_path]].concat();
+                        run_verifier(program, &args, payload)
+                    }
+                    // like Git, report the key of a signer that is not allowed without verifying it
+                    None => {
+                        let args = ["-Y", "check-novalidate", "-n", "git", "-s", signature_path];
+                        run_verifier(program, &args, payload).map(|(_, output)| (false, output))
+                    }
+                }
+            }),
+        };
+        let _ = std::fs::remove_file(&signature_file);
+        result.unwrap_or_default()
+    }
+}
+
+/// Runs `program` with `input` on stdin, returning whether it succeeded and its stdout followed by its stderr.
+fn run_verifier(program: &str, args: &[&str], input: &str) -> Option<(bool, String)> {
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .ok()?;
+    if let Some(mut stdin) = child.stdin.take() {
+        let _ = stdin.write_all(input.as_bytes());
+    }
+    let output = child.wait_with_output().ok()?;
+    let text = [output.stdout, output.stderr]
+        .map(|x| String::from_utf8_lossy(&x).to_string())
+        .join("\n");
+    Some((output.status.success(), text))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_signature() {
+        let commit = "tree 4b825dc642cb6eb9a060e54bf8d69288fbee4904\n\
+            author S <s@x> 1792224457 +0000\n\
+            committer S <s@x> 1792224457 +0000\n\
+            gpgsig -----BEGIN SSH SIGNATURE-----\n \
+            U1NIU0lHAAAAAQAAADMAAAALc3NoLWVkMjU1MTkAAAAg\n \
+            -----END SSH SIGNATURE-----\n\
+            \n\
+            message\n";
+        assert_eq!(
+            GitSignature::detect("commit", commit),
+            Some(SignatureFormat::Ssh)
+        );
+        let commit_sha256 = "tree 4b825dc6\n\
+            gpgsig-sha256 -----BEGIN PGP SIGNATURE-----\n \
+            iHUEABYKAB0WIQ\n \
+            -----END PGP SIGNATURE-----\n\
+            \n\
+            message\n";
+        assert_eq!(
+            GitSignature::detect("commit", commit_sha256),
+            Some(SignatureFormat::Gpg)
+        );
+        // an armor line in the message of a commit is not a signature
+        assert_eq!(
+            GitSignature::detect("commit", "tree 4b82\n\n-----BEGIN PGP SIGNATURE-----\n"),
+            None
+        );
+
+        let tag = "object 4b08ff4\ntype commit\ntag v1.0.0\ntagger S <s@x> 1792224457 +0000\n\n\
+            release\n-----BEGIN PGP SIGNATURE-----\n\niHUEABYKAB0WIQ\n-----END PGP SIGNATURE-----\n";
+        assert_eq!(GitSignature::detect("tag", tag), Some(SignatureFormat::Gpg));
+        assert_eq!(SignatureFormat::Gpg.to_string(), "gpg");
+    }
+
+    #[test]
+    fn test_split_signature() {
+        let commit = "tree 4b825dc6\n\
+            parent 1399e81\n\
+            gpgsig -----BEGIN SSH SIGNATURE-----\n \
+            U1NIU0lHAAAAAQ\n \n \
+            -----END SSH SIGNATURE-----\n\
+            committer S <s@x> 1792224457 +0000\n\
+            \n\
+            subject\n\nbody\n";
+        assert_eq!(
+            GitSignature::split("commit", commit),
+            Some((
+                "tree 4b825dc6\nparent 1399e81\ncommitter S <s@x> 1792224457 +0000\n\nsubject\n\nbody\n"
+                    .to_string(),
+                "-----BEGIN SSH SIGNATURE-----\nU1NIU0lHAAAAAQ\n\n-----END SSH SIGNATURE-----\n"
+                    .to_string()
+            ))
+        );
+        assert_eq!(
+            GitSignature::split("commit", "tree 4b82\n\nsubject\n"),
+            None
+        );
+
+        let tag = "object 4b08ff4\ntag v1.0.0\n\nrelease\n-----BEGIN PGP SIGNATURE-----\n\niHUE\n-----END PGP SIGNATURE-----\n";
+        let (payload, signature) = GitSignature::split("tag", tag).unwrap();
+        assert_eq!(payload, "object 4b08ff4\ntag v1.0.0\n\nrelease\n");
+        assert!(signature.starts_with("-----BEGIN PGP SIGNATURE-----\n"));
+        assert_eq!(
+            GitSignature::split("tag", "object 4b08ff4\n\nrelease\n"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_parse_key() {
+        let fingerprint = "FC898CC88C4662E9B637AE9AE6D8E3B31C34F462";
+        let valid = format!(
+            "[GNUPG:] NEWSIG\n\
+            [GNUPG:] GOODSIG E6D8E3B31C34F462 Signer <s@x>\n\
+            [GNUPG:] VALIDSIG {fingerprint} 2026-10-17 1792224457 0 4 0 22 8 00 {fingerprint}\n\
+            [GNUPG:] TRUST_ULTIMATE 0 pgp\n"
+        );
+        assert_eq!(GitSignature::parse_key(&valid), fingerprint);
+
+        let missing = format!(
+            "[GNUPG:] NEWSIG\n\
+            [GNUPG:] ERRSIG E6D8E3B31C34F462 22 8 00 1792224457 9 {fingerprint}\n\
+            [GNUPG:] NO_PUBKEY E6D8E3B31C34F462\n"
+        );
+        assert_eq!(GitSignature::parse_key(&missing), fingerprint);
+        assert_eq!(
+            GitSignature::parse_key("[GNUPG:] ERRSIG E6D8E3B31C34F462 22 8 00 1792224457 9\n"),
+            "E6D8E3B31C34F462"
+        );
+
+        assert_eq!(
+            GitSignature::parse_key(
+                "Good \"git\" signature for s@x with ED25519 key SHA256:oLLZtHOebFdz+3keJriIeUe6arFQA1VtJBa+0+lxyyU\n"
+            ),
+            "SHA256:oLLZtHOebFdz+3keJriIeUe6arFQA1VtJBa+0+lxyyU"
+        );
+        assert_eq!(
+            GitSignature::parse_key("error: gpg.ssh.allowedSignersFile needs to be configured and exist for ssh signature verification\n"),
+            ""
+        );
+    }
+}
@@ -0,0 +1,385 @@
+use std::fmt::{Display, Formatter};
+
+/// The kind of change of a file in the Git index or working tree.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileStatus {
+    Modified,
+    TypeChange,
+    Added,
+    Deleted,
+    Renamed,
+    Copied,
+}
+
+impl FileStatus {
+    /// Parses one side of the `XY` field of `git status --porcelain`, where `.` means unchanged.
+    fn from_porcelain(c: char) -> Option<Self> {
+        match c {
+            'M' => Some(FileStatus::Modified),
+            'T' => Some(FileStatus::TypeChange),
+            'A' => Some(FileStatus::Added),
+            'D' => Some(FileStatus::Deleted),
+            'R' => Some(FileStatus::Renamed),
+            'C' => Some(FileStatus::Copied),
+            _ => None,
+        }
+    }
+}
+
+impl Display for FileStatus {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FileStatus::Modified => write!(f, "modified"),
+            FileStatus::TypeChange => write!(f, "typechange"),
+            FileStatus::Added => write!(f, "added"),
+            FileStatus::Deleted => write!(f, "deleted"),
+            FileStatus::Renamed => write!(f, "renamed"),
+            FileStatus::Copied => write!(f, "copied"),
+        }
+    }
+}
+
+/// The status of a single file that differs from `HEAD`, or is untracked or conflicted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StatusEntry {
+    /// Path of the file, relative to the repository root.
+    pub path: String,
+    /// The original path of a renamed or copied file.
+    pub orig_path: Option<String>,
+    /// The change between `HEAD` and the index.
+    pub staged: Option<FileStatus>,
+    /// The change between the index and the working tree.
+    pub unstaged: Option<FileStatus>,
+    /// The file is not tracked by Git.
+    pub untracked: bool,
+    /// The file has unresolved merge conflicts.
+    pub conflicted: bool,
+}
+
+impl StatusEntry {
+    pub fn new<S: Into<String>>(path: S) -> Self {
+        StatusEntry {
+            path: path.into(),
+            orig_path: None,
+            staged: None,
+            unstaged: None,
+            untracked: false,
+            conflicted: false,
+        }
+    }
+}
+
+/// The state of a Git submodule's working tree, compared to the commit pinned by the superproject.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SubmoduleState {
+    /// The pinned commit is checked out without changes.
+    Clean,
+    /// The pinned commit is checked out, but tracked files were changed.
+    Dirty,
+    /// A different commit than the pinned one is checked out.
+    OutOfSync,
+    /// The submodule is not checked out.
+    Uninitialized,
+}
+
+impl Display for SubmoduleState {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SubmoduleState::Clean => write!(f, "clean"),
+            SubmoduleState::Dirty => write!(f, "dirty"),
+            SubmoduleState::OutOfSync => write!(f, "out-of-sync"),
+            SubmoduleState::Uninitialized => write!(f, "uninitialized"),
+        }
+    }
+}
+
+/// A Git submodule, as listed in `GIT_SUBMODULES`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GitSubmodule {
+    /// Path of the submodule, relative to the repository root.
+    pub path: String,
+    /// The commit pinned by the superproject's index.
+    pub commit: String,
+    pub state: SubmoduleState,
+}
+
+impl GitSubmodule {
+    /// Parses one line of `git submodule status --cached`, e.g. `+4b08ff4... vendor/a (heads/master)`.
+    ///
+    /// The state prefix is `-` for an uninitialized submodule, `+` for a checked out commit other
+    /// than the pinned one and `U` for merge conflicts, which are all reported as out of sync.
+    /// A dirty working tree is not visible in this output.
+    pub fn parse_status_line(line: &str) -> Option<Self> {
+        let mut chars = line.chars();
+        let state = match chars.next()? {
+            ' ' => SubmoduleState::Clean,
+            '-' => SubmoduleState::Uninitialized,
+            '+' | 'U' => SubmoduleState::OutOfSync,
+            _ => return None,
+        };
+        let (commit, path) = chars.as_str().split_once(' ')?;
+        let path = match path.rfind(" (") {
+            Some(index) if path.ends_with(')') => &path[..index],
+            _ => path,
+        };
+        Some(GitSubmodule {
+            path: path.to_string(),
+            commit: commit.to_string(),
+            state,
+        })
+    }
+}
+
+/// Whether the repository-relative `path` is equal to or under one of `prefixes`.
+/// An empty list of prefixes contains every path.
+pub(crate) fn path_in_scope(path: &str, prefixes: &[String]) -> bool {
+    prefixes.is_empty()
+        || prefixes.iter().any(|prefix| {
+            path.strip_prefix(prefix.as_str())
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+        })
+}
+
+/// The working tree status of a repository, shared by the `git` command, `git2` and `gix` backends.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct GitStatus {
+    pub entries: Vec<StatusEntry>,
+}
+
+impl GitStatus {
+    /// Parses the output of `git status --porcelain=v2 -z`.
+    ///
+    /// See <https://git-scm.com/docs/git-status#_porcelain_format_version_2>.
+    pub fn parse_porcelain_v2(input: &str) -> Self {
+        let mut status = GitStatus::default();
+        let mut fields = input.split('\0').filter(|x| !x.is_empty());
+        while let Some(line) = fields.next() {
+            let mut entry = match line.split_at_checked(2) {
+                // 1 <XY> <sub> <mH> <mI> <mW> <hH> <hI> <path>
+                Some(("1 ", rest)) => match rest.splitn(8, ' ').collect::<Vec<_>>()[..] {
+                    [xy, .., path] => Self::tracked_entry(xy, path),
+                    _ => continue,
+                },
+                // 2 <XY> <sub> <mH> <mI> <mW> <hH> <hI> <X><score> <path><NUL><origPath>
+                Some(("2 ", rest)) => match rest.splitn(9, ' ').collect::<Vec<_>>()[..] {
+                    [xy, .., path] => {
+                        let mut entry = Self::tracked_entry(xy, path);
+                        entry.orig_path = fields.next().map(|x| x.to_string());
+                        entry
+                    }
+                    _ => continue,
+                },
+                // u <XY> <sub> <m1> <m2> <m3> <mW> <h1> <h2> <h3> <path>
+                Some(("u ", rest)) => match rest.splitn(10, ' ').last() {
+                    Some(path) => StatusEntry::new(path),
+                    None => continue,
+                },
+                Some(("? ", path)) => StatusEntry::new(path),
+                _ => continue,
+            };
+            match line.chars().next() {
+                Some('u') => entry.conflicted = true,
+                Some('?') => entry.untracked = true,
+                _ => {}
+            }
+            status.entries.push(entry);
+        }
+        status
+    }
+
+    fn tracked_entry(xy: &str, path: &str) -> StatusEntry {
+        let mut xy = xy.chars();
+        let mut entry = StatusEntry::new(path);
+        entry.staged = xy.next().and_then(FileStatus::from_porcelain);
+        entry.unstaged = xy.next().and_then(FileStatus::from_porcelain);
+        entry
+    }
+
+    /// Sorts the entries by path and merges the entries of the same path into one,
+    /// for a status that reports the index and the working tree changes of a file separately.
+    pub fn merge_paths(&mut self) {
+        self.entries.sort_by(|a, b| a.path.cmp(&b.path));
+        self.entries.dedup_by(|next, entry| {
+            if next.path != entry.path {
+                return false;
+            }
+            entry.orig_path = entry.orig_path.take().or(next.orig_path.take());
+            entry.staged = entry.staged.or(next.staged);
+            entry.unstaged = entry.unstaged.or(next.unstaged);
+            entry.untracked |= next.untracked;
+            entry.conflicted |= next.conflicted;
+            true
+        });
+    }
+
+    /// Only the entries under one of the repository-relative `prefixes`, or all entries if `prefixes` is empty.
+    pub fn in_paths(&self, prefixes: &[String]) -> GitStatus {
+        GitStatus {
+            entries: self
+                .entries
+                .iter()
+                .filter(|x| path_in_scope(&x.path, prefixes))
+                .cloned()
+                .collect(),
+        }
+    }
+
+    /// Whether there are no staged, unstaged or conflicted changes. Untracked files are not taken into account.
+    pub fn is_clean(&self) -> bool {
+        self.entries.iter().all(|x| x.untracked)
+    }
+
+    /// The number of untracked files.
+    pub fn untracked_count(&self) -> usize {
+        self.entries.iter().filter(|x| x.untracked).count()
+    }
+
+    /// The number of tracked files with unstaged changes.
+    pub fn modified_count(&self) -> usize {
+        self.entries.iter().filter(|x| x.unstaged.is_some()).count()
+    }
+
+    /// The number of files with staged changes.
+    pub fn staged_count(&self) -> usize {
+        self.entries.iter().filter(|x| x.staged.is_some()).count()
+    }
+
+    /// Whether any file has unresolved merge conflicts.
+    pub fn is_conflicted(&self) -> bool {
+        self.entries.iter().any(|x| x.conflicted)
+    }
+
+    /// Formats the changed files, one per line, as used by `GIT_STATUS_FILE`.
+    ///
+    /// Example output:`  * examples/builtin_fn.rs (dirty)`
+    pub fn status_file(&self) -> String {
+        let mut concat_file = String::new();
+        let mut push = |path: &str, state: &str| {
+            concat_file.push_str("  * ");
+            concat_file.push_str(path);
+            concat_file.push_str(" (");
+            concat_file.push_str(state);
+            concat_file.push_str(")\n");
+        };
+        for entry in self.entries.iter().filter(|x| x.conflicted) {
+            push(&entry.path, "conflicted");
+        }
+        for entry in self.entries.iter().filter(|x| x.unstaged.is_some()) {
+            push(&entry.path, "dirty");
+        }
+        for entry in self.entries.iter().filter(|x| x.staged.is_some()) {
+            push(&entry.path, "staged");
+        }
+        concat_file
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_porcelain_v2() {
+        let input = [
+            "1 .M N... 100644 100644 100644 3e2ceb9 3e2ceb9 src/lib.rs",
+            "1 M. N... 100644 100644 100644 3e2ceb9 3e2ceb9 src/with space.rs",
+            "1 D. N... 100644 000000 000000 3e2ceb9 0000000 removed.rs",
+            "2 R. N... 100644 100644 100644 3e2ceb9 3e2ceb9 R100 new name.rs",
+            "old name.rs",
+            "u UU N... 100644 100644 100644 100644 3e2ceb9 3e2ceb9 3e2ceb9 conflict.rs",
+            "? untracked file.txt",
+            "! ignored.txt",
+            "",
+        ]
+        .join("\0");
+        let status = GitStatus::parse_porcelain_v2(&input);
+        assert_eq!(status.entries.len(), 6);
+
+        assert_eq!(status.entries[0].path, "src/lib.rs");
+        assert_eq!(status.entries[0].staged, None);
+        assert_eq!(status.entries[0].unstaged, Some(FileStatus::Modified));
+        assert_eq!(status.entries[1].path, "src/with space.rs");
+        assert_eq!(status.entries[1].staged, Some(FileStatus::Modified));
+        assert_eq!(status.entries[2].staged, Some(FileStatus::Deleted));
+        assert_eq!(status.entries[3].path, "new name.rs");
+        assert_eq!(status.entries[3].orig_path.as_deref(), Some("old name.rs"));
+        assert_eq!(status.entries[3].staged, Some(FileStatus::Renamed));
+        assert_eq!(status.entries[4].path, "conflict.rs");
+        assert!(status.entries[4].conflicted);
+        assert_eq!(status.entries[5].path, "untracked file.txt");
+        assert!(status.entries[5].untracked);
+
+        assert!(!status.is_clean());
+        assert!(status.is_conflicted());
+        assert_eq!(status.untracked_count(), 1);
+        assert_eq!(status.modified_count(), 1);
+        assert_eq!(status.staged_count(), 3);
+        assert_eq!(
+            status.status_file(),
+            "  * conflict.rs (conflicted)\n  * src/lib.rs (dirty)\n  * src/with space.rs (staged)\n  * removed.rs (staged)\n  * new name.rs (staged)\n"
+        );
+    }
+
+    #[test]
+    fn test_in_paths() {
+        let input = "1 .M N... 100644 100644 100644 3e2ceb9 3e2ceb9 crates/cli/src/main.rs\0\
+            1 .M N... 100644 100644 100644 3e2ceb9 3e2ceb9 crates/client/lib.rs\0\
+            ? crates/cli\0";
+        let status = GitStatus::parse_porcelain_v2(input);
+        let scoped = status.in_paths(&["crates/cli".to_string()]);
+        assert_eq!(scoped.entries.len(), 2);
+        assert_eq!(scoped.modified_count(), 1);
+        assert_eq!(scoped.untracked_count(), 1);
+        assert_eq!(status.in_paths(&[]), status);
+    }
+
+    #[test]
+    fn test_merge_paths() {
+        let mut staged = StatusEntry::new("src/lib.rs");
+        staged.staged = Some(FileStatus::Renamed);
+        staged.orig_path = Some("src/old.rs".to_string());
+        let mut unstaged = StatusEntry::new("src/lib.rs");
+        unstaged.unstaged = Some(FileStatus::Modified);
+        let mut untracked = StatusEntry::new("notes.txt");
+        untracked.untracked = true;
+        let mut status = GitStatus {
+            entries: vec![staged, untracked.clone(), unstaged],
+        };
+        status.merge_paths();
+        let mut merged = StatusEntry::new("src/lib.rs");
+        merged.staged = Some(FileStatus::Renamed);
+        merged.unstaged = Some(FileStatus::Modified);
+        merged.orig_path = Some("src/old.rs".to_string());
+        assert_eq!(status.entries, [untracked, merged]);
+    }
+
+    #[test]
+    fn test_parse_submodule_status() {
+        let commit = "4b08ff450d16b5edbd3c5f5ccdb01cd51e4a4144";
+        let parse = |line: String| GitSubmodule::parse_status_line(&line).unwrap();
+
+        let submodule = parse(format!("+{commit} vendor/a (heads/master)"));
+        assert_eq!(submodule.path, "vendor/a");
+        assert_eq!(submodule.commit, commit);
+        assert_eq!(submodule.state, SubmoduleState::OutOfSync);
+
+        let submodule = parse(format!(" {commit} vendor/b dir (v1.0.0-2-g4b08ff4)"));
+        assert_eq!(submodule.path, "vendor/b dir");
+        assert_eq!(submodule.state, SubmoduleState::Clean);
+
+        let submodule = parse(format!("-{commit} vendor/c"));
+        assert_eq!(submodule.path, "vendor/c");
+        assert_eq!(submodule.state, SubmoduleState::Uninitialized);
+        assert_eq!(submodule.state.to_string(), "uninitialized");
+
+        assert_eq!(GitSubmodule::parse_status_line(""), None);
+    }
+
+    #[test]
+    fn test_clean_status() {
+        let status = GitStatus::parse_porcelain_v2("? untracked.txt\0");
+        assert!(status.is_clean());
+        assert!(status.status_file().is_empty());
+        assert!(GitStatus::parse_porcelain_v2("").is_clean());
+    }
+}
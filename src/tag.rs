@@ -0,0 +1,446 @@
+use crate::signature::SignatureFormat;
+use crate::{SdResult, ShadowError};
+
+/// Selects the Git tags used for [`TAG`](crate::TAG), [`LAST_TAG`](crate::LAST_TAG) and
+/// [`COMMITS_SINCE_TAG`](crate::COMMITS_SINCE_TAG), like `git describe --match <glob> --exclude <glob>`.
+///
+/// This is useful in a repository with tags for several crates, such as `cli-v1.2.0` and `server-v3.0.1`.
+/// With a strip-prefix, [`LAST_TAG`](crate::LAST_TAG) holds the bare version:
+///
+/// ```
+/// use shadow_rs::TagPattern;
+///
+/// let pattern = TagPattern::new("cli-v*").exclude("*-rc*").strip_prefix("cli-v");
+/// assert_eq!(pattern.apply("cli-v1.2.0").as_deref(), Some("1.2.0"));
+/// assert_eq!(pattern.apply("cli-v1.3.0-rc1"), None);
+/// assert_eq!(pattern.apply("server-v3.0.1"), None);
+/// ```
+///
+/// Globs support `*`, `?` and `[...]`, with the same meaning as in `git describe --match`.
+#[derive(Debug, Default, Clone)]
+pub struct TagPattern {
+    include: Vec<String>,
+    exclude: Vec<String>,
+    strip_prefix: Option<String>,
+}
+
+impl TagPattern {
+    /// Creates a pattern that only selects tags matching the glob `include`.
+    pub fn new(include: &str) -> Self {
+        Self::default().include(include)
+    }
+
+    /// Also selects tags matching the glob `include`.
+    pub fn include(mut self, include: &str) -> Self {
+        self.include.push(include.to_string());
+        self
+    }
+
+    /// Never selects tags matching the glob `exclude`, even if they match an include glob.
+    pub fn exclude(mut self, exclude: &str) -> Self {
+        self.exclude.push(exclude.to_string());
+        self
+    }
+
+    /// Removes `prefix` from the selected tags before they are stored in the constants.
+    pub fn strip_prefix(mut self, prefix: &str) -> Self {
+        self.strip_prefix = Some(prefix.to_string());
+        self
+    }
+
+    /// Gets the include globs. All tags are included if there are none.
+    pub fn get_include(&self) -> &[String] {
+        &self.include
+    }
+
+    /// Gets the exclude globs.
+    pub fn get_exclude(&self) -> &[String] {
+        &self.exclude
+    }
+
+    /// Gets the prefix removed from the selected tags, if any.
+    pub fn get_strip_prefix(&self) -> Option<&str> {
+        self.strip_prefix.as_deref()
+    }
+
+    /// Whether the tag `name` is selected by this pattern.
+    pub fn matches(&self, name: &str) -> bool {
+        (self.include.is_empty() || self.include.iter().any(|x| glob_match(x, name)))
+            && !self.exclude.iter().any(|x| glob_match(x, name))
+    }
+
+    /// Returns the tag `name` without the strip-prefix, or `None` if it is not selected by this pattern.
+    pub fn apply(&self, name: &str) -> Option<String> {
+        if !self.matches(name) {
+            return None;
+        }
+        let name = match &self.strip_prefix {
+            Some(prefix) => name.strip_prefix(prefix.as_str()).unwrap_or(name),
+            None => name,
+        };
+        Some(name.to_string())
+    }
+
+    /// Applies the pattern to a list of tags, one per line, as printed by `git tag -l`.
+    pub(crate) fn apply_lines(&self, names: &str) -> String {
+        names
+            .lines()
+            .filter_map(|x| self.apply(x.trim()))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// The regular expression selecting the same tags, for the `latesttag(pattern)` function of
+    /// Mercurial templates, or `None` if this pattern selects all tags.
+    pub(crate) fn hg_regex(&self) -> Option<String> {
+        if self.include.is_empty() && self.exclude.is_empty() {
+            return None;
+        }
+        let any = |globs: &[String]| {
+            let regexes: Vec<String> = globs.iter().map(|x| glob_regex(x)).collect();
+            format!("(?:{})", regexes.join("|"))
+        };
+        let include = if self.include.is_empty() {
+            ".*".to_string()
+        } else {
+            any(&self.include)
+        };
+        let exclude = if self.exclude.is_empty() {
+            String::new()
+        } else {
+            format!("(?!{}$)", any(&self.exclude))
+        };
+        Some(format!("^{exclude}{include}$"))
+    }
+
+    /// The `--match` and `--exclude` arguments for `git describe`.
+    pub(crate) fn describe_args(&self) -> Vec<&str> {
+        let include = self.include.iter().flat_map(|x| ["--match", x.as_str()]);
+        let exclude = self.exclude.iter().flat_map(|x| ["--exclude", x.as_str()]);
+        include.chain(exclude).collect()
+    }
+}
+
+/// The metadata of an annotated tag, as emitted in [`TAG_MESSAGE`](crate::TAG_MESSAGE),
+/// [`TAG_TAGGER`](crate::TAG_TAGGER) and [`TAG_DATE`](crate::TAG_DATE).
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct AnnotatedTag {
+    pub tagger: String,
+    pub tagger_email: String,
+    /// The tag time as a Unix timestamp.
+    pub timestamp: i64,
+    /// The timezone of the tagger, in seconds east of UTC.
+    pub offset: i32,
+    /// The tag message without its signature.
+    pub message: String,
+}
+
+impl AnnotatedTag {
+    /// Parses a raw tag object, as printed by `git cat-file tag <name>`.
+    ///
+    /// Returns `None` for an object without a `tagger` header, such as a lightweight tag's commit.
+    pub fn parse(object: &str) -> Option<Self> {
+        let (header, message) = object.split_once("\n\n").unwrap_or((object, ""));
+        let tagger = header.lines().find_map(|x| x.strip_prefix("tagger "))?;
+        // tagger <name> <<email>> <timestamp> <+hhmm>
+        let (name, rest) = tagger.split_once(" <")?;
+        let (email, rest) = rest.split_once("> ")?;
+        let (timestamp, offset) = rest.trim().split_once(' ')?;
+        let sign = if offset.starts_with('-') { -1 } else { 1 };
+        let offset = offset.trim_start_matches(['+', '-']);
+        let hours = offset.get(0..2)?.parse::<i32>().ok()?;
+        let minutes = offset.get(2..4)?.parse::<i32>().ok()?;
+
+        let message: Vec<&str> = message
+            .lines()
+            .take_while(|x| SignatureFormat::from_armor(x).is_none())
+            .collect();
+        Some(AnnotatedTag {
+            tagger: name.trim().to_string(),
+            tagger_email: email.to_string(),
+            timestamp: timestamp.parse().ok()?,
+            offset: sign * (hours * 3600 + minutes * 60),
+            message: message.join("\n").trim().to_string(),
+        })
+    }
+}
+
+/// What to do when the tag of a build does not match the package version, see [`check_tag_version`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum TagCheck {
+    /// Do not compare the tag with the package version.
+    #[default]
+    Off,
+    /// Emit a `cargo:warning` on a mismatch.
+    Warn,
+    /// Fail the build script with a [`ShadowError`] on a mismatch.
+    Deny,
+}
+
+/// Checks that one of the `tags` pointing exactly at HEAD, one per line as printed by
+/// `git tag --points-at HEAD`, matches the package version `pkg_version`.
+/// Tags on later commits, which [`TAG`](crate::TAG) may list, are not passed to the check.
+/// The tags are compared after the [`TagPattern`] strip-prefix and an optional leading `v`,
+/// so that both `v2.3.0` and `cli-v2.3.0` match `2.3.0`.
+///
+/// Builds that are not at a tag always pass the check.
+pub fn check_tag_version(tags: &str, pkg_version: &str, check: TagCheck) -> SdResult<()> {
+    let tags: Vec<&str> = tags
+        .lines()
+        .map(str::trim)
+        .filter(|x| !x.is_empty())
+        .collect();
+    if check == TagCheck::Off
+        || tags.is_empty()
+        || tags
+            .iter()
+            .any(|x| x.strip_prefix(['v', 'V']).unwrap_or(x) == pkg_version)
+    {
+        return Ok(());
+    }
+    let msg = format!(
+        "The tag `{}` does not match the package version `{pkg_version}`",
+        tags.join("`, `")
+    );
+    match check {
+        TagCheck::Deny => Err(ShadowError::String(msg)),
+        _ => {
+            println!("cargo:warning={msg}");
+            Ok(())
+        }
+    }
+}
+
+/// Matches `text` against a glob supporting `*`, `?` and `[...]` classes with ranges and `!`/`^` negation.
+/// As with `git describe --match`, `*` also matches `/`.
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let (mut p, mut t) = (0, 0);
+    // the position after the last `*` and the text position it is tried against, for backtracking
+    let mut star: Option<(usize, usize)> = None;
+    while t < text.len() {
+        let step = match pattern.get(p) {
+            Some('*') => {
+                star = Some((p + 1, t));
+                p += 1;
+                continue;
+            }
+            Some('?') => Some(p + 1),
+            Some('[') => match_class(&pattern, p, text[t]),
+            Some(c) if *c == text[t] => Some(p + 1),
+            _ => None,
+        };
+        match (step, star) {
+            (Some(next), _) => {
+                p = next;
+                t += 1;
+            }
+            (None, Some((star_p, star_t))) => {
+                p = star_p;
+                t = star_t + 1;
+                star = Some((star_p, star_t + 1));
+            }
+            (None, None) => return false,
+        }
+    }
+    pattern[p..].iter().all(|x| *x == '*')
+}
+
+/// Matches `c` against the class starting at `pattern[start] == '['`,
+/// returning the position after the class if it matches.
+fn match_class(pattern: &[char], start: usize, c: char) -> Option<usize> {
+    let Some(class) = parse_class(pattern, start) else {
+        // an unterminated class matches a literal `[`
+        return (c == '[').then_some(start + 1);
+    };
+    let matched = class.ranges.iter().any(|(from, to)| *from <= c && c <= *to);
+    (matched != class.negate).then_some(class.next)
+}
+
+/// A `[...]` class of a glob.
+struct GlobClass {
+    negate: bool,
+    /// The inclusive character ranges, a single character being a range of itself.
+    ranges: Vec<(char, char)>,
+    /// The position after the class in the glob.
+    next: usize,
+}
+
+/// Parses the class starting at `pattern[start] == '['`, or returns `None` if it is unterminated.
+fn parse_class(pattern: &[char], start: usize) -> Option<GlobClass> {
+    let mut i = start + 1;
+    let negate = matches!(pattern.get(i), Some('!') | Some('^'));
+    if negate {
+        i += 1;
+    }
+    let mut ranges = vec![];
+    let mut first = true;
+    while let Some(&x) = pattern.get(i) {
+        if x == ']' && !first {
+            return Some(GlobClass {
+                negate,
+                ranges,
+                next: i + 1,
+            });
+        }
+        first = false;
+        match (pattern.get(i + 1), pattern.get(i + 2)) {
+            (Some('-'), Some(&end)) if end != ']' => {
+                ranges.push((x, end));
+                i += 3;
+            }
+            _ => {
+                ranges.push((x, x));
+                i += 1;
+            }
+        }
+    }
+    None
+}
+
+/// Translates the glob `pattern` into a Python regular expression, as used by Mercurial.
+/// Every character other than an ASCII letter, digit or `_` is escaped as `\xNN`,
+/// so that the result can be embedded in a raw template string.
+fn glob_regex(pattern: &str) -> String {
+    let escape = |c: char| match c {
+        c if c.is_ascii_alphanumeric() || c == '_' || !c.is_ascii() => c.to_string(),
+        c => format!("\\x{:02x}", c as u32),
+    };
+    let pattern: Vec<char> = pattern.chars().collect();
+    let mut regex = String::new();
+    let mut i = 0;
+    while let Some(&c) = pattern.get(i) {
+        i += 1;
+        match c {
+            '*' => regex.push_str(".*"),
+            '?' => regex.push('.'),
+            '[' => match parse_class(&pattern, i - 1) {
+                Some(class) => {
+                    regex.push_str(if class.negate { "[^" } else { "[" });
+                    for (from, to) in class.ranges {
+                        regex.push_str(&escape(from));
+                        if from != to {
+                            regex.push('-');
+                            regex.push_str(&escape(to));
+                        }
+                    }
+                    regex.push(']');
+                    i = class.next;
+                }
+                None => regex.push_str(&escape(c)),
+            },
+            c => regex.push_str(&escape(c)),
+        }
+    }
+    regex
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("*", ""));
+        assert!(glob_match("v*", "v1.0.0"));
+        assert!(glob_match("cli-v*", "cli-v1.2.0"));
+        assert!(!glob_match("cli-v*", "server-v3.0.1"));
+        assert!(glob_match("*-rc*", "cli-v1.3.0-rc1"));
+        assert!(glob_match("v?.*", "v1.0"));
+        assert!(!glob_match("v?.*", "v10.0"));
+        assert!(glob_match("v[0-9]*", "v1.0.0"));
+        assert!(!glob_match("v[!0-9]*", "v1.0.0"));
+        assert!(glob_match("release/*", "release/a/b"));
+        assert!(glob_match("a*b*c", "axxbyyc"));
+        assert!(!glob_match("a*b*c", "axxbyy"));
+        assert!(glob_match("[", "["));
+    }
+
+    #[test]
+    fn test_check_tag_version() {
+        assert!(check_tag_version("v2.3.0", "2.2.9", TagCheck::Off).is_ok());
+        assert!(check_tag_version("", "2.2.9", TagCheck::Deny).is_ok());
+        assert!(check_tag_version("v2.3.0", "2.3.0", TagCheck::Deny).is_ok());
+        assert!(check_tag_version("2.3.0", "2.3.0", TagCheck::Deny).is_ok());
+        assert!(check_tag_version("latest\nv2.3.0", "2.3.0", TagCheck::Deny).is_ok());
+        assert!(check_tag_version("v2.3.0", "2.2.9", TagCheck::Warn).is_ok());
+        let err = check_tag_version("v2.3.0", "2.2.9", TagCheck::Deny).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "The tag `v2.3.0` does not match the package version `2.2.9`"
+        );
+    }
+
+    #[test]
+    fn test_tag_pattern() {
+        let pattern = TagPattern::default();
+        assert_eq!(pattern.apply("v1.0.0").as_deref(), Some("v1.0.0"));
+        assert!(pattern.describe_args().is_empty());
+        assert_eq!(pattern.hg_regex(), None);
+
+        let pattern = TagPattern::new("cli-v*")
+            .include("cli/v*")
+            .exclude("*-rc*")
+            .strip_prefix("cli-v");
+        assert_eq!(pattern.apply("cli-v1.2.0").as_deref(), Some("1.2.0"));
+        assert_eq!(pattern.apply("cli/v1.2.0").as_deref(), Some("cli/v1.2.0"));
+        assert_eq!(pattern.apply("cli-v1.3.0-rc1"), None);
+        assert_eq!(
+            pattern.apply_lines("cli-v1.2.0\nserver-v3.0.1\ncli-v1.2.1\n"),
+            "1.2.0\n1.2.1"
+        );
+        assert_eq!(
+            pattern.describe_args(),
+            [
+                "--match",
+                "cli-v*",
+                "--match",
+                "cli/v*",
+                "--exclude",
+                "*-rc*"
+            ]
+        );
+        assert_eq!(
+            pattern.hg_regex().as_deref(),
+            Some(r"^(?!(?:.*\x2drc.*)$)(?:cli\x2dv.*|cli\x2fv.*)$")
+        );
+
+        let pattern = TagPattern::default().exclude("v[!0-1]?");
+        assert_eq!(
+            pattern.hg_regex().as_deref(),
+            Some(r"^(?!(?:v[^0-1].)$).*$")
+        );
+    }
+
+    #[test]
+    fn test_parse_annotated_tag() {
+        let object = "object 4b08ff450d16b5edbd3c5f5ccdb01cd51e4a4144\n\
+            type commit\n\
+            tag v1.0.0\n\
+            tagger Jane Doe <jane@example.com> 1628080443 +0530\n\
+            \n\
+            Release 1.0.0\n\
+            \n\
+            - First stable release\n\
+            -----BEGIN PGP SIGNATURE-----\n\
+            \n\
+            iHUEABYKAB0WIQ\n\
+            -----END PGP SIGNATURE-----\n";
+        let tag = AnnotatedTag::parse(object).unwrap();
+        assert_eq!(tag.tagger, "Jane Doe");
+        assert_eq!(tag.tagger_email, "jane@example.com");
+        assert_eq!(tag.timestamp, 1628080443);
+        assert_eq!(tag.offset, 5 * 3600 + 30 * 60);
+        assert_eq!(tag.message, "Release 1.0.0\n\n- First stable release");
+
+        let tag = AnnotatedTag::parse("object 4b08ff4\ntagger A <a@x> 0 -0800\n\nfix").unwrap();
+        assert_eq!(tag.offset, -8 * 3600);
+        assert_eq!(tag.message, "fix");
+
+        assert_eq!(
+            AnnotatedTag::parse("tree 4b825dc\nauthor A <a@x> 0 +0000\n\nmsg"),
+            None
+        );
+    }
+}
@@ -0,0 +1,534 @@
+use crate::build::ShadowConst;
+use crate::date_time::DateTime;
+use crate::err::SdResult;
+use crate::git::*;
+use crate::status::{FileStatus, GitStatus, StatusEntry};
+use crate::tag::TagPattern;
+use const_format::concatcp;
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+pub(crate) const VCS_KIND_DOC: &str = r#"
+The version control system that this project was built from: `git`, `hg` or `jj`.
+This constant will be `none` if no repository could be detected."#;
+pub const VCS_KIND: ShadowConst = "VCS_KIND";
+
+/// [`VcsKind`] holds the types of version control systems that `shadow-rs` can collect from.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum VcsKind {
+    Git,
+    Mercurial,
+    Jujutsu,
+    #[default]
+    None,
+}
+
+impl Display for VcsKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            VcsKind::Git => write!(f, "git"),
+            VcsKind::Mercurial => write!(f, "hg"),
+            VcsKind::Jujutsu => write!(f, "jj"),
+            VcsKind::None => write!(f, "none"),
+        }
+    }
+}
+
+/// Information about the commit that the project was built from, as collected by a [`VcsBackend`].
+#[derive(Default)]
+pub struct VcsHeadInfo {
+    pub commit: String,
+    pub short_commit: String,
+    pub author: String,
+    pub email: String,
+    pub committer: String,
+    pub committer_email: String,
+    /// The committer date.
+    pub date: Option<DateTime>,
+    pub author_date: Option<DateTime>,
+    pub branch: String,
+    pub tag: String,
+    pub last_tag: String,
+    pub commits_since_tag: Option<usize>,
+    /// The raw commit message, see [`Git::update_message`].
+    pub message: String,
+}
+
+/// A version control system that can fill the repository constants such as [`BRANCH`],
+/// [`COMMIT_HASH`] and [`GIT_CLEAN`].
+///
+/// A backend can be selected with [`ShadowBuilder::vcs_backend`](crate::ShadowBuilder::vcs_backend).
+/// Otherwise, `shadow-rs` picks one of [`JjBackend`], [`HgBackend`] or [`GitBackend`]
+/// depending on whether a `.jj`, `.hg` or `.git` directory is found in the nearest repository root.
+pub trait VcsBackend {
+    /// The kind of version control system, written to [`VCS_KIND`].
+    fn kind(&self) -> VcsKind;
+
+    /// Returns `true` if `path` is inside a repository managed by this backend.
+    fn detect(&self, path: &Path) -> bool;
+
+    /// Fills the repository constants of `git` for the repository containing `path`.
+    fn init(&self, git: &mut Git, path: &Path, std_env: &BTreeMap<String, String>) -> SdResult<()>;
+}
+
+/// Returns `true` if `path` or one of its parents contains `name`.
+fn ancestor_contains(path: &Path, name: &str) -> bool {
+    let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+    path.ancestors().any(|x| x.join(name).exists())
+}
+
+/// The root of the repository containing `path`, i.e. the nearest directory with a `.git`, `.hg` or `.jj` entry.
+pub(crate) fn repo_root(path: &Path) -> Option<PathBuf> {
+    let path = path.canonicalize().ok()?;
+    path.ancestors()
+        .find(|x| {
+            [".git", ".hg", ".jj"]
+                .iter()
+                .any(|name| x.join(name).exists())
+        })
+        .map(Path::to_path_buf)
+}
+
+/// The default backend, using `git2`, `gix` or the `git` command depending on the enabled features.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct GitBackend;
+
+impl VcsBackend for GitBackend {
+    fn kind(&self) -> VcsKind {
+        VcsKind::Git
+    }
+
+    fn detect(&self, path: &Path) -> bool {
+        ancestor_contains(path, ".git")
+    }
+
+    fn init(
+        &self,
+        git: &mut Git,
+        path: &Path,
+        _std_env: &BTreeMap<String, String>,
+    ) -> SdResult<()> {
+        git.init(path)
+    }
+}
+
+/// Mercurial backend, using the `hg` command.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct HgBackend;
+
+const HG_LOG_TEMPLATE: &str =
+    "{node}\n{node|short}\n{author|person}\n{author|email}\n{date|rfc3339date}\n{branch}\n{tags}\n{latesttag}\n{latesttag % '{distance} '}\n{desc}";
+
+/// The `hg log` template, where `latesttag` only selects the tags matching `pattern`.
+/// It lists every tag of the latest tagged ancestor joined by `:`, then the distance to each of them.
+fn hg_log_template(pattern: &TagPattern) -> String {
+    match pattern.hg_regex() {
+        Some(regex) => HG_LOG_TEMPLATE.replace("latesttag", &format!("latesttag(r're:{regex}')")),
+        None => HG_LOG_TEMPLATE.to_string(),
+    }
+}
+
+impl VcsBackend for HgBackend {
+    fn kind(&self) -> VcsKind {
+        VcsKind::Mercurial
+    }
+
+    fn detect(&self, path: &Path) -> bool {
+        ancestor_contains(path, ".hg")
+    }
+
+    fn init(
+        &self,
+        git: &mut Git,
+        path: &Path,
+        _std_env: &BTreeMap<String, String>,
+    ) -> SdResult<()> {
+        let template = hg_log_template(git.get_tag_pattern());
+        let log = exec("hg", path, &["log", "-r", ".", "--template", &template])
+            .ok_or("Failed to exec hg log")?;
+        let info = parse_hg_log(&log);
+        git.update_head(&info);
+
+        // `hg status` has no staging area; files scheduled for addition or removal
+        // are reported as staged, everything else as dirty.
+        if let Some(status) = exec(
+            "hg",
+            path,
+            &["status", "--modified", "--added", "--removed", "--deleted"],
+        ) {
+            git.update_status(&parse_status_lines(&status, |c| match c {
+                'M' => Some((FileStatus::Modified, false)),
+                'A' => Some((FileStatus::Added, true)),
+                'R' => Some((FileStatus::Deleted, true)),
+                '!' => Some((FileStatus::Deleted, false)),
+                _ => None,
+            }));
+        }
+        Ok(())
+    }
+}
+
+/// Jujutsu backend, using the `jj` command.
+///
+/// The parent of the working-copy commit (`@-`) is treated as the commit that the project was built from,
+/// and the changes in the working-copy commit (`@`) as the dirty files, just like `HEAD` and the working tree in Git.
+///
+/// Every `jj` command runs with `--ignore-working-copy`, so that building never snapshots the working copy
+/// into a new operation. The dirty files are therefore those recorded by the last `jj` command.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct JjBackend;
+
+const JJ_BOOKMARKS_TEMPLATE: &str = r#"local_bookmarks.map(|b| b.name()).join(" ")"#;
+const JJ_TAGS_TEMPLATE: &str = r#"tags.map(|t| t.name()).join(" ")"#;
+const JJ_LOG_TEMPLATE: &str = concatcp!(
+    r#"commit_id ++ "\n" ++ commit_id.short(8) ++ "\n" ++ author.name() ++ "\n" ++ author.email() ++ "\n" ++ author.timestamp().format("%Y-%m-%dT%H:%M:%S%:z") ++ "\n" ++ committer.name() ++ "\n" ++ committer.email() ++ "\n" ++ committer.timestamp().format("%Y-%m-%dT%H:%M:%S%:z") ++ "\n" ++ "#,
+    JJ_BOOKMARKS_TEMPLATE,
+    r#" ++ "\n" ++ "#,
+    JJ_TAGS_TEMPLATE,
+    r#" ++ "\n" ++ description"#
+);
+const JJ_TAG_LINES_TEMPLATE: &str = concatcp!(JJ_TAGS_TEMPLATE, r#" ++ "\n""#);
+
+impl VcsBackend for JjBackend {
+    fn kind(&self) -> VcsKind {
+        VcsKind::Jujutsu
+    }
+
+    fn detect(&self, path: &Path) -> bool {
+        ancestor_contains(path, ".jj")
+    }
+
+    fn init(
+        &self,
+        git: &mut Git,
+        path: &Path,
+        _std_env: &BTreeMap<String, String>,
+    ) -> SdResult<()> {
+        let jj = |args: &[&str]| {
+            let mut all = vec!["--ignore-working-copy", "--no-pager", "--color=never"];
+            all.extend_from_slice(args);
+            exec("jj", path, &all)
+        };
+        let jj_log = |revset: &str, template: &str| {
+            jj(&[
+                "log",
+                "--no-graph",
+                "--limit",
+                "1",
+                "-r",
+                revset,
+                "-T",
+                template,
+            ])
+        };
+
+        let log = jj_log("@-", JJ_LOG_TEMPLATE).ok_or("Failed to exec jj log")?;
+        let mut info = parse_jj_log(&log);
+
+        // The nearest bookmark and tag when the parent commit carries none itself.
+        if info.branch.is_empty() {
+            let bookmarks = jj_log("latest(::@- & bookmarks())", JJ_BOOKMARKS_TEMPLATE);
+            info.branch = first_word(&bookmarks.unwrap_or_default());
+        }
+        // The tags of every ancestor, newest first, of which the first one selected by the pattern
+        // is the last tag, like `git describe --match`.
+        let tags = jj(&[
+            "log",
+            "--no-graph",
+            "-r",
+            "::@- & tags()",
+            "-T",
+            JJ_TAG_LINES_TEMPLATE,
+        ]);
+        let last_tag = first_matching_tag(&tags.unwrap_or_default(), git.get_tag_pattern());
+        if !last_tag.is_empty() {
+            let range = format!("{}..@-", quote_revset(&last_tag));
+            let commits = jj(&["log", "--no-graph", "-r", &range, "-T", r#""x\n""#]);
+            info.commits_since_tag = commits.map(|x| x.lines().count());
+            info.last_tag = last_tag;
+        }
+        git.update_head(&info);
+
+        if let Some(status) = jj(&["diff", "--summary", "-r", "@"]) {
+            git.update_status(&parse_status_lines(&status, |c| match c {
+                'M' => Some((FileStatus::Modified, false)),
+                'A' => Some((FileStatus::Added, false)),
+                'D' => Some((FileStatus::Deleted, false)),
+                'R' => Some((FileStatus::Renamed, false)),
+                'C' => Some((FileStatus::Copied, false)),
+                _ => None,
+            }));
+        }
+        Ok(())
+    }
+}
+
+/// Picks the backend for the nearest repository root containing `path`, so that a repository
+/// nested inside another one is detected as itself.
+/// Jujutsu is checked first, since colocated `jj` repositories also contain a `.git` directory.
+pub(crate) fn detect_backend(path: &Path) -> Option<Box<dyn VcsBackend>> {
+    let root = repo_root(path)?;
+    let backends: [(&str, Box<dyn VcsBackend>); 3] = [
+        (".jj", Box::new(JjBackend)),
+        (".hg", Box::new(HgBackend)),
+        (".git", Box::new(GitBackend)),
+    ];
+    backends
+        .into_iter()
+        .find(|(name, _)| root.join(name).exists())
+        .map(|(_, backend)| backend)
+}
+
+/// Quotes `name` as a revset string literal, so that a tag such as `v1.0-rc` is not parsed as an expression.
+fn quote_revset(name: &str) -> String {
+    format!("\"{}\"", name.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn exec(program: &str, path: &Path, args: &[&str]) -> Option<String> {
+    Command::new(program)
+        .current_dir(path)
+        .args(args)
+        .output()
+        .ok()
+        .filter(|x| x.status.success())
+        .and_then(|x| String::from_utf8(x.stdout).ok())
+        .map(|x| x.trim().to_string())
+}
+
+fn first_word(s: &str) -> String {
+    s.split_whitespace().next().unwrap_or_default().to_string()
+}
+
+/// The first of the whitespace-separated `tags` that is selected by `pattern`.
+fn first_matching_tag(tags: &str, pattern: &TagPattern) -> String {
+    tags.split_whitespace()
+        .find(|x| pattern.matches(x))
+        .unwrap_or_default()
+        .to_string()
+}
+
+fn parse_hg_log(log: &str) -> VcsHeadInfo {
+    let mut lines = log.lines();
+    let mut next = || lines.next().unwrap_or_default().trim().to_string();
+    let commit = next();
+    let mut short_commit = next();
+    short_commit.truncate(8);
+    let author = next();
+    let email = next();
+    let date = DateTime::from_iso8601_string(&next()).ok();
+    let branch = next();
+    let tag = next()
+        .split_whitespace()
+        .filter(|x| *x != "tip")
+        .collect::<Vec<_>>()
+        .join("\n");
+    // several tags of the same changeset are joined by `:`, each followed by its distance
+    let last_tag = next()
+        .split(':')
+        .next()
+        .filter(|x| *x != "null")
+        .unwrap_or_default()
+        .to_string();
+    let commits_since_tag = first_word(&next())
+        .parse()
+        .ok()
+        .filter(|_| !last_tag.is_empty());
+    let message = lines.collect::<Vec<_>>().join("\n");
+    // Mercurial only records who committed a changeset and when
+    VcsHeadInfo {
+        commit,
+        short_commit,
+        committer: author.clone(),
+        committer_email: email.clone(),
+        author,
+        email,
+        author_date: date.clone(),
+        date,
+        branch,
+        tag,
+        last_tag,
+        commits_since_tag,
+        message,
+    }
+}
+
+fn parse_jj_log(log: &str) -> VcsHeadInfo {
+    let mut lines = log.lines();
+    let mut next = || lines.next().unwrap_or_default().trim().to_string();
+    let commit = next();
+    let short_commit = next();
+    let author = next();
+    let email = next();
+    let author_date = DateTime::from_iso8601_string(&next()).ok();
+    let committer = next();
+    let committer_email = next();
+    let date = DateTime::from_iso8601_string(&next()).ok();
+    let branch = next()
+        .split_whitespace()
+        .next()
+        .unwrap_or_default()
+        .to_string();
+    let tag = next().split_whitespace().collect::<Vec<_>>().join("\n");
+    let message = lines.collect::<Vec<_>>().join("\n");
+    VcsHeadInfo {
+        commit,
+        short_commit,
+        author,
+        email,
+        committer,
+        committer_email,
+        date,
+        author_date,
+        branch,
+        tag,
+        message,
+        ..Default::default()
+    }
+}
+
+/// Parses `<status> <path>` lines, where `kind` maps a status letter
+/// to the kind of change and whether it is staged.
+fn parse_status_lines(
+    status: &str,
+    kind: impl Fn(char) -> Option<(FileStatus, bool)>,
+) -> GitStatus {
+    let mut git_status = GitStatus::default();
+    for line in status.lines() {
+        let mut chars = line.chars();
+        let (Some(Some((file_status, staged))), Some(' ')) =
+            (chars.next().map(&kind), chars.next())
+        else {
+            continue;
+        };
+        let mut entry = StatusEntry::new(chars.as_str());
+        if staged {
+            entry.staged = Some(file_status);
+        } else {
+            entry.unstaged = Some(file_status);
+        }
+        git_status.entries.push(entry);
+    }
+    git_status
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Format;
+
+    #[test]
+    fn test_parse_hg_log() {
+        let log = "0123456789abcdef0123456789abcdef01234567\n0123456789ab\nbaoyachi\nxxx@gmail.com\n2021-08-04T12:34:03+08:00\ndefault\nv1.0.0 tip\nv1.0.0\n0\nFix build\n\nSigned-off-by: baoyachi";
+        let info = parse_hg_log(log);
+        assert_eq!(info.commit, "0123456789abcdef0123456789abcdef01234567");
+        assert_eq!(info.short_commit, "01234567");
+        assert_eq!(info.author, "baoyachi");
+        assert_eq!(info.email, "xxx@gmail.com");
+        assert_eq!(
+            info.date.unwrap().human_format(),
+            "2021-08-04 12:34:03 +08:00"
+        );
+        assert_eq!(info.branch, "default");
+        assert_eq!(info.tag, "v1.0.0");
+        assert_eq!(info.last_tag, "v1.0.0");
+        assert_eq!(info.commits_since_tag, Some(0));
+        assert_eq!(info.message, "Fix build\n\nSigned-off-by: baoyachi");
+
+        let log = "0123\n0123\nbaoyachi\nxxx@gmail.com\n2021-08-04T12:34:03+08:00\ndefault\ntip\ncli-v1.1:cli-v1.1-final\n2 2 ";
+        let info = parse_hg_log(log);
+        assert_eq!(info.last_tag, "cli-v1.1");
+        assert_eq!(info.commits_since_tag, Some(2));
+
+        let log =
+            "0123\n0123\nbaoyachi\nxxx@gmail.com\n2021-08-04T12:34:03+08:00\ndefault\ntip\nnull\n3";
+        let info = parse_hg_log(log);
+        assert_eq!(info.tag, "");
+        assert_eq!(info.last_tag, "");
+        assert_eq!(info.commits_since_tag, None);
+    }
+
+    #[test]
+    fn test_hg_log_template() {
+        assert_eq!(hg_log_template(&TagPattern::default()), HG_LOG_TEMPLATE);
+        let template = hg_log_template(&TagPattern::new("cli-v*"));
+        let latest = r"latesttag(r're:^(?:cli\x2dv.*)$')";
+        assert!(template.contains(&format!("\n{{{latest}}}\n{{{latest} % '{{distance}} '}}\n")));
+    }
+
+    #[test]
+    fn test_jj_last_tag() {
+        // `::@- & tags()` lists the tags of each commit, newest first
+        let tags = "server-v2.0.0\ncli-v1.1.0-rc1 cli-v1.1.0\nserver-v1.0.0\ncli-v1.0.0\n";
+        let pattern = TagPattern::new("cli-v*").exclude("*-rc*");
+        assert_eq!(first_matching_tag(tags, &pattern), "cli-v1.1.0");
+        assert_eq!(
+            first_matching_tag(tags, &TagPattern::default()),
+            "server-v2.0.0"
+        );
+        assert_eq!(first_matching_tag(tags, &TagPattern::new("web-v*")), "");
+    }
+
+    #[test]
+    fn test_parse_jj_log() {
+        let log = "0123456789abcdef0123456789abcdef01234567\n01234567\nbaoyachi\nxxx@gmail.com\n2021-08-04T12:34:03-05:00\nsomeone\nyyy@gmail.com\n2021-08-05T09:00:00+02:00\nmain dev\nv1.0.0";
+        let info = parse_jj_log(log);
+        assert_eq!(info.short_commit, "01234567");
+        assert_eq!(info.author, "baoyachi");
+        assert_eq!(info.committer, "someone");
+        assert_eq!(info.committer_email, "yyy@gmail.com");
+        assert_eq!(
+            info.author_date.unwrap().human_format(),
+            "2021-08-04 12:34:03 -05:00"
+        );
+        assert_eq!(
+            info.date.unwrap().human_format(),
+            "2021-08-05 09:00:00 +02:00"
+        );
+        assert_eq!(info.branch, "main");
+        assert_eq!(info.tag, "v1.0.0");
+    }
+
+    #[test]
+    fn test_parse_status_lines() {
+        let status = "M src/lib.rs\nA src/new file.rs\nR old.rs\n! gone.rs\n? unknown.rs\n";
+        let status = parse_status_lines(status, |c| match c {
+            'M' => Some((FileStatus::Modified, false)),
+            'A' => Some((FileStatus::Added, true)),
+            'R' => Some((FileStatus::Deleted, true)),
+            '!' => Some((FileStatus::Deleted, false)),
+            _ => None,
+        });
+        assert_eq!(status.entries.len(), 4);
+        assert_eq!(status.modified_count(), 2);
+        assert_eq!(status.staged_count(), 2);
+        assert_eq!(status.entries[1].path, "src/new file.rs");
+        assert_eq!(status.entries[3].unstaged, Some(FileStatus::Deleted));
+    }
+
+    #[test]
+    fn test_detect_backend() {
+        let backend = detect_backend(Path::new("./")).unwrap();
+        assert_eq!(backend.kind(), VcsKind::Git);
+        assert_eq!(VcsKind::Jujutsu.to_string(), "jj");
+
+        // the nearest repository wins over one in a parent directory
+        let dir = std::env::temp_dir().join(format!("shadow-rs-vcs-{}", std::process::id()));
+        let nested = dir.join("nested");
+        std::fs::create_dir_all(dir.join(".hg")).unwrap();
+        std::fs::create_dir_all(nested.join(".git")).unwrap();
+        let kind = |path: &Path| detect_backend(path).map(|x| x.kind());
+        assert_eq!(kind(&nested), Some(VcsKind::Git));
+        assert_eq!(kind(&dir), Some(VcsKind::Mercurial));
+        std::fs::create_dir_all(nested.join(".jj")).unwrap();
+        assert_eq!(kind(&nested), Some(VcsKind::Jujutsu));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_quote_revset() {
+        assert_eq!(quote_revset("v1.0-rc"), r#""v1.0-rc""#);
+        assert_eq!(quote_revset(r#"a"b\c"#), r#""a\"b\\c""#);
+    }
+}